@@ -8,7 +8,10 @@ use std::{
 
 use crate::Revert;
 
-pub trait Action: Any + ActionClone + Debug {}
+/// An action that can be applied to a [`State`](crate::State)
+///
+/// Actions are `Send`, so that a [`Revert`] can be moved to another thread along with its state
+pub trait Action: Any + ActionClone + Debug + Send {}
 
 pub trait ActionClone {
     fn dyn_clone(&self) -> Box<dyn Action>;
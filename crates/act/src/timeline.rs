@@ -41,6 +41,25 @@ impl Revert {
             },
         }
     }
+
+    /// Iterate over the reverting actions, in the order they were recorded, which is the reverse
+    /// of the order they must be applied in
+    pub fn actions(&self) -> impl Iterator<Item = &AnyAction> {
+        match self {
+            Self::None => [].iter(),
+            Self::Apply(apply) => apply.0.iter(),
+        }
+    }
+
+    /// Consume `self`, returning the actions in the order they must be applied to revert the state
+    ///
+    /// Actions are recorded in the order they were performed, reverting them is done backwards
+    pub fn into_actions(self) -> Vec<AnyAction> {
+        match self {
+            Self::None => Vec::new(),
+            Self::Apply(apply) => apply.0.into_iter().rev().collect(),
+        }
+    }
 }
 
 impl From<Vec<Revert>> for Revert {
@@ -0,0 +1,109 @@
+//! Run every bundled example for a fixed number of steps
+//!
+//! ```sh
+//! cargo bench -p grai --bench examples
+//! ```
+//!
+//! The interpreter only runs on the dense [`Grid`], the `fetch` group measures what the dense
+//! storage changes against a baseline : fetching the word under the head at every step of an
+//! example, from the grid and its cached words, and from a [`HashMapGrid`] decoding the cell on
+//! each read, as the grid did before.
+
+use std::collections::HashMap;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use grai::{Cell, Frame, Grid, Position, Word, examples};
+
+const STEPS: usize = 10_000;
+
+fn run(mut frame: Frame) -> Frame {
+    for _ in 0..STEPS {
+        if frame.step().is_err() {
+            break;
+        }
+    }
+
+    frame
+}
+
+fn examples(c: &mut Criterion) {
+    let mut group = c.benchmark_group("examples");
+
+    for name in examples::names() {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || Frame::from_example(name).unwrap(),
+                run,
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+/// The baseline storage, a map of the non-empty cells
+struct HashMapGrid(HashMap<Position, Cell>);
+
+impl HashMapGrid {
+    fn new(grid: &Grid) -> Self {
+        Self(
+            grid.cells()
+                .map(|(position, cell)| (position, cell.clone()))
+                .collect(),
+        )
+    }
+
+    fn word(&self, position: Position) -> Option<Word> {
+        self.0
+            .get(&position)
+            .map(|cell| Word::from_cell(cell.clone()))
+    }
+}
+
+/// Every position of the head while running `frame`
+fn trace(mut frame: Frame) -> Vec<Position> {
+    let mut positions = Vec::with_capacity(STEPS);
+
+    for _ in 0..STEPS {
+        positions.push(frame.head.position);
+        if frame.step().is_err() {
+            break;
+        }
+    }
+
+    positions
+}
+
+fn fetch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fetch");
+
+    for name in examples::names() {
+        let frame = Frame::from_example(name).unwrap();
+        let positions = trace(Frame::from_example(name).unwrap());
+
+        group.bench_function(BenchmarkId::new("dense", name), |b| {
+            b.iter(|| {
+                positions
+                    .iter()
+                    .filter_map(|position| frame.grid.word(*position))
+                    .count()
+            })
+        });
+
+        let baseline = HashMapGrid::new(&frame.grid);
+        group.bench_function(BenchmarkId::new("hashmap", name), |b| {
+            b.iter(|| {
+                positions
+                    .iter()
+                    .filter_map(|position| baseline.word(*position))
+                    .count()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, examples, fetch);
+criterion_main!(benches);
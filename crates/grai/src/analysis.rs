@@ -0,0 +1,449 @@
+//! Abstract execution of a [`Frame`], shared by the static analyses ([`lint`](crate::lint) and
+//! [`flow`](crate::flow))
+//!
+//! The head is walked from its starting position, following every path it could take, while
+//! keeping track of what is known of the stack. Values computed at runtime (the result of `add`,
+//! `equ`…) are unknown, so a `jif` on such a value explores both branches.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    CustomOpcodes, Direction, Frame, Grid, Literal, Opcode, Operand, Position, Word,
+    lint::{Lint, LintKind},
+};
+
+/// A position of the head in the grid, regardless of the stack
+pub(crate) type HeadKey = (Position, Direction);
+
+/// How the head went from a cell to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Flow {
+    /// The head stepped in its direction
+    Step,
+
+    /// The head was moved by a `jmp` or a `jif`
+    Jump,
+}
+
+/// Why the head could not go further
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Stop {
+    /// The program halts, see [`Break::Halted`](crate::Break::Halted)
+    Halt,
+
+    /// The program errors
+    Error,
+
+    /// Where the head goes can't be known statically
+    Unknown,
+}
+
+/// Everything learned by walking a [`Frame`]
+#[derive(Debug)]
+pub(crate) struct Analysis {
+    /// Cells the head can reach
+    pub visited: HashSet<Position>,
+
+    /// Cells a `set` can write to
+    pub written: HashSet<Position>,
+
+    pub transitions: HashSet<(HeadKey, HeadKey, Flow)>,
+    pub stops: HashSet<(HeadKey, Stop)>,
+
+    pub lints: Vec<Lint>,
+
+    /// `false` if some paths could not be followed
+    pub complete: bool,
+}
+
+/// Walk `frame` until the set of written cells is stable
+pub(crate) fn analyze(frame: &Frame) -> Analysis {
+    // Writes found by a walk can change what an earlier part of the same walk read, walk again
+    // until no new written cell is found
+    let mut written = HashSet::new();
+
+    loop {
+        let walk = Walk::run(frame, &written);
+        if walk.written.len() == written.len() {
+            return Analysis {
+                visited: walk.visited,
+                written: walk.written,
+                transitions: walk.transitions,
+                stops: walk.stops,
+                lints: walk.lints,
+                complete: walk.complete,
+            };
+        }
+
+        written = walk.written;
+    }
+}
+
+/// A value on the abstract stack, `None` if it is only known at runtime
+type Value = Option<Operand>;
+
+/// What is known of the stack at some point of the walk
+#[derive(Debug, Clone)]
+struct AbstractStack {
+    values: Vec<Value>,
+
+    /// `true` if unknown values lie below `values`, because the stack grew too deep to be tracked
+    unknown_below: bool,
+}
+
+impl AbstractStack {
+    const MAX_DEPTH: usize = 64;
+
+    fn push(&mut self, value: Value) {
+        if self.values.len() >= Self::MAX_DEPTH {
+            self.values.remove(0);
+            self.unknown_below = true;
+        }
+
+        self.values.push(value);
+    }
+
+    /// Pop a value, returns `None` on a stack underflow
+    fn pop(&mut self) -> Option<Value> {
+        self.values
+            .pop()
+            .or_else(|| self.unknown_below.then_some(None))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HeadState {
+    position: Position,
+    direction: Direction,
+    stack: AbstractStack,
+}
+
+impl HeadState {
+    fn head_key(&self) -> HeadKey {
+        (self.position, self.direction)
+    }
+
+    fn key(&self) -> (HeadKey, Vec<Option<String>>, bool) {
+        (
+            self.head_key(),
+            self.stack
+                .values
+                .iter()
+                .map(|value| value.as_ref().map(Operand::to_string))
+                .collect(),
+            self.stack.unknown_below,
+        )
+    }
+
+    fn step(mut self) -> Self {
+        // Just like the head, stay in place when stepping outside of the grid
+        if let Ok(position) = self.position.checked_step(self.direction, 1) {
+            self.position = position;
+        }
+
+        self
+    }
+
+    fn move_to(mut self, position: Position) -> Self {
+        self.position = position;
+        self
+    }
+}
+
+/// An abstract run of the head, following every possible path
+struct Walk<'a> {
+    grid: &'a Grid,
+    opcodes: &'a CustomOpcodes,
+
+    /// Cells known to be written while walking
+    written: HashSet<Position>,
+
+    /// Cells written by a previous walk, their content is unknown
+    unknown: &'a HashSet<Position>,
+
+    visited: HashSet<Position>,
+    transitions: HashSet<(HeadKey, HeadKey, Flow)>,
+    stops: HashSet<(HeadKey, Stop)>,
+    lints: Vec<Lint>,
+    complete: bool,
+}
+
+impl<'a> Walk<'a> {
+    const MAX_STATES: usize = 1 << 16;
+
+    fn run(frame: &'a Frame, unknown: &'a HashSet<Position>) -> Self {
+        let mut walk = Self {
+            grid: &frame.grid,
+            opcodes: &frame.opcodes,
+            written: unknown.clone(),
+            unknown,
+            visited: HashSet::new(),
+            transitions: HashSet::new(),
+            stops: HashSet::new(),
+            lints: Vec::new(),
+            complete: true,
+        };
+
+        let initial = HeadState {
+            position: frame.head.position,
+            direction: frame.head.direction,
+            stack: AbstractStack {
+                values: frame.stack.iter().cloned().map(Some).collect(),
+                unknown_below: false,
+            },
+        };
+
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([initial]);
+
+        while let Some(state) = queue.pop_front() {
+            if !seen.insert(state.key()) {
+                continue;
+            }
+
+            if seen.len() > Self::MAX_STATES {
+                walk.complete = false;
+                break;
+            }
+
+            walk.visited.insert(state.position);
+
+            let from = state.head_key();
+            for (next, flow) in walk.next_states(state) {
+                walk.transitions.insert((from, next.head_key(), flow));
+                queue.push_back(next);
+            }
+        }
+
+        walk
+    }
+
+    fn lint(&mut self, position: Position, kind: LintKind) {
+        self.lints.push(Lint::new(position, kind));
+    }
+
+    fn stop(&mut self, state: &HeadState, stop: Stop) {
+        if stop == Stop::Unknown {
+            self.complete = false;
+        }
+
+        self.stops.insert((state.head_key(), stop));
+    }
+
+    /// Resolve pointers until a non pointer operand is found, returns `None` if it can't be known
+    /// statically
+    fn resolve(&self, value: Value) -> Value {
+        let mut operand = value?;
+        let mut visited = HashSet::new();
+
+        while let Operand::Pointer(pointer) = operand {
+            let position = *pointer.position();
+            if self.unknown.contains(&position) || !visited.insert(position) {
+                return None;
+            }
+
+            operand = Operand::from_cell(self.grid.get(position));
+        }
+
+        Some(operand)
+    }
+
+    /// Read the literal at `position`, as `Address::fetch_literal()` would
+    fn fetch_literal(&self, position: Position) -> Option<Literal> {
+        if self.unknown.contains(&position) {
+            None
+        } else {
+            Some(Literal::from_cell(self.grid.get(position)))
+        }
+    }
+
+    /// Pop a value for `opcode`, linting a stack underflow
+    fn pop(&mut self, state: &mut HeadState, opcode: Opcode) -> Option<Value> {
+        let value = state.stack.pop();
+        if value.is_none() {
+            self.lint(state.position, LintKind::StackUnderflow { opcode });
+        }
+
+        value
+    }
+
+    /// Pop an address for `opcode`
+    ///
+    /// Returns `Err` if the program would error, `Ok(None)` if the address can't be known
+    fn pop_address(
+        &mut self,
+        state: &mut HeadState,
+        opcode: Opcode,
+    ) -> Result<Option<Position>, ()> {
+        let value = self.pop(state, opcode).ok_or(())?;
+
+        match self.resolve(value) {
+            Some(Operand::Address(address)) => Ok(Some(*address.position())),
+            Some(got) => {
+                self.lint(state.position, LintKind::NotAnAddress { opcode, got });
+                Err(())
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Pop a condition for `opcode`
+    ///
+    /// Returns `Err` if the program would error, `Ok(None)` if the condition can't be known
+    fn pop_condition(&mut self, state: &mut HeadState, opcode: Opcode) -> Result<Option<bool>, ()> {
+        let value = self.pop(state, opcode).ok_or(())?;
+
+        let literal = match self.resolve(value) {
+            Some(Operand::Literal(literal)) => Some(literal),
+            Some(Operand::Address(address)) => self.fetch_literal(*address.position()),
+            Some(Operand::Errored(_)) => return Ok(Some(false)),
+            Some(Operand::Pointer(_)) | None => None,
+        };
+
+        match literal.map(|literal| literal.try_as_bool()) {
+            Some(Ok(condition)) => Ok(Some(condition)),
+            Some(Err(_)) => Err(()),
+            None => Ok(None),
+        }
+    }
+
+    fn jump(
+        &mut self,
+        state: HeadState,
+        opcode: Opcode,
+        target: Option<Position>,
+    ) -> Vec<(HeadState, Flow)> {
+        match target {
+            Some(target) => {
+                if self.grid.get(target).is_empty() && !self.written.contains(&target) {
+                    self.lint(state.position, LintKind::JumpIntoEmpty { opcode, target });
+                }
+
+                vec![(state.move_to(target), Flow::Jump)]
+            }
+            None => {
+                self.lint(state.position, LintKind::UnknownTarget { opcode });
+                self.stop(&state, Stop::Unknown);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Every state the head can be in after executing the cell under it in `state`
+    fn next_states(&mut self, mut state: HeadState) -> Vec<(HeadState, Flow)> {
+        if self.unknown.contains(&state.position) {
+            self.lint(state.position, LintKind::ModifiedCode);
+            self.stop(&state, Stop::Unknown);
+            return Vec::new();
+        }
+
+        let cell = self.grid.get(state.position);
+        if cell.is_empty() {
+            let position = state.position;
+            let next = state.step();
+
+            if next.position == position {
+                self.stop(&next, Stop::Halt);
+                return Vec::new();
+            } else {
+                return vec![(next, Flow::Step)];
+            }
+        }
+
+        let word = Word::from_cell(cell);
+        let opcode = match self.opcodes.named_by(&word).map_or(word, Word::Custom) {
+            Word::Opcode(opcode) => opcode,
+            // What a custom opcode does can't be known statically
+            Word::Custom(_) => {
+                self.stop(&state, Stop::Unknown);
+                return Vec::new();
+            }
+            Word::Operand(operand) => {
+                state.stack.push(Some(operand));
+                return vec![(state.step(), Flow::Step)];
+            }
+        };
+
+        match self.evaluate(&mut state, opcode) {
+            Ok(next) => next,
+            Err(()) => {
+                self.stop(&state, Stop::Error);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Abstract counterpart of [`Opcode::evaluate()`]
+    fn evaluate(
+        &mut self,
+        state: &mut HeadState,
+        opcode: Opcode,
+    ) -> Result<Vec<(HeadState, Flow)>, ()> {
+        use Opcode::*;
+
+        match opcode {
+            Nop => {}
+
+            Gup | Gri | Gdo | Gle => {
+                state.direction = match opcode {
+                    Gup => Direction::Up,
+                    Gri => Direction::Right,
+                    Gdo => Direction::Down,
+                    Gle => Direction::Left,
+                    _ => unreachable!(),
+                };
+            }
+
+            Set => {
+                let at = self.pop_address(state, opcode)?;
+                self.pop(state, opcode).ok_or(())?;
+
+                match at {
+                    Some(position) => {
+                        self.written.insert(position);
+                    }
+                    None => {
+                        self.complete = false;
+                        self.lint(state.position, LintKind::UnknownTarget { opcode });
+                    }
+                }
+            }
+
+            Add | Sub | Mul | Div | Equ | Neq | Grt | Lst | Grq | Lsq | Rnd => {
+                self.pop(state, opcode).ok_or(())?;
+                self.pop(state, opcode).ok_or(())?;
+
+                state.stack.push(None);
+            }
+
+            // What a host function pushes can't be known statically
+            Cal => {
+                self.stop(state, Stop::Unknown);
+                return Ok(Vec::new());
+            }
+
+            Jmp => {
+                let target = self.pop_address(state, opcode)?;
+                return Ok(self.jump(state.clone(), opcode, target));
+            }
+
+            Jif => {
+                let target = self.pop_address(state, opcode)?;
+                let condition = self.pop_condition(state, opcode)?;
+
+                let step = (state.clone().step(), Flow::Step);
+                return Ok(match condition {
+                    Some(true) => self.jump(state.clone(), opcode, target),
+                    Some(false) => vec![step],
+                    None => {
+                        let mut next = self.jump(state.clone(), opcode, target);
+                        next.push(step);
+                        next
+                    }
+                });
+            }
+        }
+
+        Ok(vec![(state.clone().step(), Flow::Step)])
+    }
+}
@@ -0,0 +1,389 @@
+//! Import programs written for the archived interpreter (`graliffer_archive`)
+//!
+//! The archived interpreter stored frames as JSON, with a slightly different layout, and used
+//! different names for some of its opcodes. [`import()`] converts such a frame to a current
+//! [`Frame`], renaming opcodes, and reports every cell that could not be faithfully converted as
+//! an [`ImportIssue`].
+//!
+//! # Opcodes
+//! | archive              | current |
+//! |----------------------|---------|
+//! | `gou`                | `gup`   |
+//! | `gor`                | `gri`   |
+//! | `god`                | `gdo`   |
+//! | `gol`                | `gle`   |
+//! | `ijp`                | `jif`   |
+//! | `igu`, `igr`, `igd`, `igl` | *none*  |
+//! | `prt`, `dbg`, `hlt`  | *none*  |
+//!
+//! Every other opcode kept its name. Cells holding an opcode with no equivalent are left
+//! untouched, they will be read as literals by the current interpreter.
+//!
+//! # Behaviour
+//! Some opcodes kept their meaning but not all of their behaviour, every cell holding one of
+//! them is reported as an [`ImportIssueKind::BehaviourChanged`] :
+//! | opcodes                    | archive                                   | current                                 |
+//! |----------------------------|-------------------------------------------|-----------------------------------------|
+//! | `ijp` → `jif`              | the head stays in place when false        | the head steps when false               |
+//! | `ijp` → `jif`              | a condition other than `0` is true        | a condition other than `0`/`1` errors   |
+//! | `add`, `sub`, `mul`, `div` | a non numeric operand is `0`              | a non numeric operand errors            |
+//! | `add`, `sub`, `mul`, `div` | overflows and divisions by zero give `0`  | they give an errored operand            |
+//! | `grt`, `lst`, `grq`, `lsq` | a non numeric operand is `0`              | a non numeric operand errors            |
+//!
+//! # Examples
+//! ```
+//! # use grai::archive;
+//! let json = r#"{
+//!     "head": { "position": "AA", "direction": "Right" },
+//!     "grid": { "AA": "gou", "BA": "prt", "CA": "jif" },
+//!     "stack": { "data": [{ "Literal": { "value": "abc" } }] }
+//! }"#;
+//!
+//! let import = archive::import(json).unwrap();
+//! assert_eq!(import.frame.grid.get("AA".parse().unwrap()).as_str(), "gup");
+//! assert_eq!(import.issues.len(), 2);
+//! ```
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{Address, Cell, Errored, Frame, Head, Literal, Opcode, Operand, Pointer, Position};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveImportError {
+    #[error("invalid json : {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid archive frame at `{}` : {}", .0.path(), .0.inner())]
+    Deserialize(#[from] serde_path_to_error::Error<serde_json::Error>),
+}
+
+/// A cell that could not be faithfully converted
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("at {} : {kind}", position.as_textual_string())]
+pub struct ImportIssue {
+    pub position: Position,
+    pub kind: ImportIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ImportIssueKind {
+    #[error("opcode `{0}` has no equivalent, it is kept as is and will be read as a literal")]
+    UnsupportedOpcode(String),
+
+    #[error("literal `{0}` is now read as an opcode")]
+    ReadAsOpcode(String),
+
+    #[error("literal `{0}` is now read as an errored operand")]
+    ReadAsErrored(String),
+
+    #[error("opcode `{0}` behaves differently : {1}")]
+    BehaviourChanged(String, BehaviourChange),
+}
+
+/// How an opcode of the archived interpreter behaves differently once converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum BehaviourChange {
+    #[error(
+        "the head now steps when the condition is false, and a condition other than `0` or `1` \
+        is an error instead of true"
+    )]
+    ConditionalJump,
+
+    #[error(
+        "a non numeric operand is now an error instead of `0`, overflows and divisions by zero \
+        now give an errored operand instead of `0`"
+    )]
+    Arithmetic,
+
+    #[error("a non numeric operand is now an error instead of `0`")]
+    Comparison,
+}
+
+/// The result of an [`import()`]
+#[derive(Debug)]
+pub struct ArchiveImport {
+    pub frame: Frame,
+
+    /// Every issue encountered, ordered by row then column
+    pub issues: Vec<ImportIssue>,
+}
+
+/// Convert an archive frame, in its JSON form, to a current [`Frame`]
+///
+/// # Errors
+/// Returns an error if the JSON is not a valid archive frame, the error holds the path to the
+/// faulty value
+pub fn import(json: &str) -> Result<ArchiveImport, ArchiveImportError> {
+    import_value(serde_json::from_str(json)?)
+}
+
+/// Same as [`import()`], from an already parsed JSON [`Value`](serde_json::Value)
+pub fn import_value(value: serde_json::Value) -> Result<ArchiveImport, ArchiveImportError> {
+    let archive: ArchiveFrame = serde_path_to_error::deserialize(value)?;
+
+    let mut frame = Frame {
+        head: archive.head,
+        stack: archive
+            .stack
+            .data
+            .into_iter()
+            .map(ArchiveOperand::into_operand)
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut issues = Vec::new();
+    for (position, content) in archive.grid {
+        let (content, issue) = convert_cell(&content);
+
+        if let Some(kind) = issue {
+            issues.push(ImportIssue { position, kind });
+        }
+
+        frame.grid.set(position, content);
+    }
+
+    issues.sort_by_key(|issue| {
+        let (x, y) = issue.position.as_numeric();
+        (y, x)
+    });
+
+    Ok(ArchiveImport { frame, issues })
+}
+
+/// Opcodes of the archived interpreter, which only recognized them in lowercase
+const ARCHIVE_OPCODES: [&str; 25] = [
+    "dbg", "hlt", "nop", "gou", "gor", "god", "gol", "jmp", "igu", "igr", "igd", "igl", "ijp",
+    "add", "sub", "mul", "div", "equ", "neq", "grt", "lst", "grq", "lsq", "set", "prt",
+];
+
+fn convert_cell(content: &str) -> (Cell, Option<ImportIssueKind>) {
+    let cell = Cell::new_trim(content);
+
+    if ARCHIVE_OPCODES.contains(&cell.as_str()) {
+        let renamed = match cell.as_str() {
+            "gou" => "gup",
+            "gor" => "gri",
+            "god" => "gdo",
+            "gol" => "gle",
+            "ijp" => {
+                let issue = ImportIssueKind::BehaviourChanged(
+                    "ijp".to_string(),
+                    BehaviourChange::ConditionalJump,
+                );
+                return (Cell::new_trim("jif"), Some(issue));
+            }
+            changed @ ("add" | "sub" | "mul" | "div" | "grt" | "lst" | "grq" | "lsq") => {
+                let change = match changed {
+                    "add" | "sub" | "mul" | "div" => BehaviourChange::Arithmetic,
+                    _ => BehaviourChange::Comparison,
+                };
+
+                let issue = ImportIssueKind::BehaviourChanged(changed.to_string(), change);
+                return (cell.clone(), Some(issue));
+            }
+            unsupported @ ("igu" | "igr" | "igd" | "igl" | "prt" | "dbg" | "hlt") => {
+                let issue = ImportIssueKind::UnsupportedOpcode(unsupported.to_string());
+                return (cell.clone(), Some(issue));
+            }
+            same => same,
+        };
+
+        (Cell::new_trim(renamed), None)
+    } else if Opcode::from_cell(cell.clone()).is_ok() {
+        let issue = ImportIssueKind::ReadAsOpcode(cell.to_string());
+        (cell, Some(issue))
+    } else if Errored::from_ref_cell(&cell).is_ok() {
+        let issue = ImportIssueKind::ReadAsErrored(cell.to_string());
+        (cell, Some(issue))
+    } else {
+        (cell, None)
+    }
+}
+
+#[derive(Deserialize)]
+struct ArchiveFrame {
+    head: Head,
+    grid: HashMap<Position, String>,
+    stack: ArchiveStack,
+}
+
+#[derive(Deserialize)]
+struct ArchiveStack {
+    data: Vec<ArchiveOperand>,
+}
+
+#[derive(Deserialize)]
+enum ArchiveOperand {
+    Literal { value: String },
+    Address { position: Position },
+    Pointer { position: Position },
+}
+
+impl ArchiveOperand {
+    fn into_operand(self) -> Operand {
+        match self {
+            Self::Literal { value } => Literal::from_cell(Cell::new_trim(&value)).into(),
+            Self::Address { position } => Address::from_position(position).into(),
+            Self::Pointer { position } => Pointer::from_position(position).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use act::State;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Direction, FrameAction};
+
+    #[test]
+    fn opcodes() {
+        let import = import_value(json!({
+            "head": { "position": "AA", "direction": "Down" },
+            "grid": {
+                "AA": "gou", "BA": "gor", "CA": "god", "DA": "gol",
+                "AB": "ijp", "BB": "jmp", "CB": "add", "DB": "abc",
+                "AC": "igu", "BC": "prt", "CC": "dbg", "DC": "hlt",
+                "AD": "gup", "BD": "ADD", "CD": "###"
+            },
+            "stack": { "data": [] }
+        }))
+        .unwrap();
+
+        let cell = |position: &str| import.frame.grid.get(position.parse().unwrap());
+        assert_eq!(cell("AA").as_str(), "gup");
+        assert_eq!(cell("BA").as_str(), "gri");
+        assert_eq!(cell("CA").as_str(), "gdo");
+        assert_eq!(cell("DA").as_str(), "gle");
+        assert_eq!(cell("AB").as_str(), "jif");
+        assert_eq!(cell("BB").as_str(), "jmp");
+        assert_eq!(cell("CB").as_str(), "add");
+        assert_eq!(cell("DB").as_str(), "abc");
+        assert_eq!(cell("BC").as_str(), "prt");
+
+        let issue = |position: &str, kind| ImportIssue {
+            position: position.parse().unwrap(),
+            kind,
+        };
+        assert_eq!(
+            import.issues,
+            vec![
+                issue(
+                    "AB",
+                    ImportIssueKind::BehaviourChanged(
+                        "ijp".into(),
+                        BehaviourChange::ConditionalJump
+                    )
+                ),
+                issue(
+                    "CB",
+                    ImportIssueKind::BehaviourChanged("add".into(), BehaviourChange::Arithmetic)
+                ),
+                issue("AC", ImportIssueKind::UnsupportedOpcode("igu".into())),
+                issue("BC", ImportIssueKind::UnsupportedOpcode("prt".into())),
+                issue("CC", ImportIssueKind::UnsupportedOpcode("dbg".into())),
+                issue("DC", ImportIssueKind::UnsupportedOpcode("hlt".into())),
+                issue("AD", ImportIssueKind::ReadAsOpcode("gup".into())),
+                issue("BD", ImportIssueKind::ReadAsOpcode("ADD".into())),
+                issue("CD", ImportIssueKind::ReadAsErrored("###".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn behaviour_changes() {
+        let import = import_value(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": {
+                "AA": "sub", "BA": "mul", "CA": "div",
+                "AB": "grt", "BB": "lst", "CB": "grq", "DB": "lsq",
+                "AC": "equ", "BC": "neq", "CC": "set", "DC": "jmp"
+            },
+            "stack": { "data": [] }
+        }))
+        .unwrap();
+
+        let changes = import
+            .issues
+            .into_iter()
+            .map(|issue| match issue.kind {
+                ImportIssueKind::BehaviourChanged(opcode, change) => (opcode, change),
+                kind => panic!("expected a behaviour change, got {kind}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            [
+                ("sub".into(), BehaviourChange::Arithmetic),
+                ("mul".into(), BehaviourChange::Arithmetic),
+                ("div".into(), BehaviourChange::Arithmetic),
+                ("grt".into(), BehaviourChange::Comparison),
+                ("lst".into(), BehaviourChange::Comparison),
+                ("grq".into(), BehaviourChange::Comparison),
+                ("lsq".into(), BehaviourChange::Comparison),
+            ]
+        );
+    }
+
+    #[test]
+    fn run_imported() {
+        let mut import = import_value(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "1", "BA": "@AB", "CA": "ijp", "AB": "god" },
+            "stack": { "data": [
+                { "Literal": { "value": "abc" } },
+                { "Address": { "position": "CC" } },
+                { "Pointer": { "position": "DD" } }
+            ] }
+        }))
+        .unwrap();
+        assert_eq!(
+            import.issues,
+            [ImportIssue {
+                position: "CA".parse().unwrap(),
+                kind: ImportIssueKind::BehaviourChanged(
+                    "ijp".into(),
+                    BehaviourChange::ConditionalJump
+                ),
+            }]
+        );
+
+        let frame = &mut import.frame;
+        assert_eq!(
+            frame
+                .stack
+                .iter()
+                .map(Operand::to_string)
+                .collect::<Vec<_>>(),
+            ["abc", "@CC", "&DD"]
+        );
+
+        for _ in 0..4 {
+            let _ = frame.act(FrameAction::Step).unwrap();
+        }
+        assert_eq!(frame.head.position, "AC".parse().unwrap());
+        assert_eq!(frame.head.direction, Direction::Down);
+    }
+
+    #[test]
+    fn invalid() {
+        let err = import_value(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": {},
+            "stack": { "data": [{ "Address": { "position": "A" } }] }
+        }))
+        .unwrap_err();
+
+        match err {
+            ArchiveImportError::Deserialize(err) => {
+                assert_eq!(err.path().to_string(), "stack.data[0].Address.position")
+            }
+            _ => panic!("expected a deserialize error, got {err}"),
+        }
+    }
+}
@@ -0,0 +1,722 @@
+//! An assembler, laying out a linear source on the grid
+//!
+//! Writing cells directly on the grid means recomputing every `@XY` by hand after each edit.
+//! Instead, the assembler reads a program as a sequence of cells, places them one after the
+//! other in the layout direction, and resolves labels into addresses and pointers.
+//!
+//! # Format
+//! A source is read line by line, `;` starts a comment that runs to the end of the line. Each line
+//! holds any number of labels, followed by either cells, or a directive.
+//!
+//! - a label is a name followed by `:`, such as `loop:`, it designates the position of the next
+//!   placed cell. A name is made of ASCII letters, digits and `_`, it must not start with a digit,
+//!   and must not be a valid [`Position`] (like `AB`), to avoid any confusion with addresses
+//! - cells are separated by whitespaces, each is placed at the cursor, which then moves one cell
+//!   in the layout direction :
+//!   - `@name` and `&name` are replaced by an address, or a pointer, to the label `name`
+//!   - a JSON string, such as `"a b"`, is placed as its content, for contents containing
+//!     whitespaces, `;`, or starting with `"`
+//!   - anything else is placed as is, it must fit in a [`Cell`]
+//! - a directive is a keyword starting with `.`, followed by its arguments :
+//!   - `.org XY`, move the cursor to `XY`
+//!   - `.dir direction`, change the layout direction, without placing anything
+//!   - `.turn direction`, place the opcode turning the head to `direction` (`gup`, `gri`, `gdo`
+//!     or `gle`), then lay out the following cells in that direction
+//!   - `.skip [count]`, move the cursor `count` cells (1 by default), leaving them empty
+//!   - `.head`, the head starts at the cursor, going in the layout direction. By default, the
+//!     head starts on the first placed cell
+//!   - `.stack cells…`, the operands on the stack, from bottom to top, labels are resolved
+//!
+//! The cursor starts at `AA`, laying out cells to the right.
+//!
+//! # Example
+//! ```
+//! # use grai::asm;
+//! let source = "
+//!     ; decrement the counter until it reaches 0
+//!     loop: @counter 1 sub @counter set
+//!           @counter 0 neq @loop jif
+//!
+//!     .org AB
+//!     counter: 10
+//! ";
+//!
+//! let assembly = asm::assemble(source).unwrap();
+//! let frame = assembly.frame;
+//!
+//! assert_eq!(frame.grid.get("AA".parse().unwrap()).as_str(), "@AB");
+//! assert_eq!(frame.grid.get("IA".parse().unwrap()).as_str(), "@AA");
+//! assert_eq!(assembly.labels["counter"], "AB".parse().unwrap());
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    Address, Cell, CellError, Direction, Frame, Head, Opcode, Operand, Pointer, Position,
+    PositionError,
+    text::{ArgumentError, unquote},
+};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("line {line}: {kind}")]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    #[error("unknown directive `{0}`")]
+    UnknownDirective(String),
+
+    #[error("missing {argument} for directive `{directive}`")]
+    MissingArgument {
+        directive: &'static str,
+        argument: &'static str,
+    },
+
+    #[error("unexpected argument `{got}` for directive `{directive}`")]
+    UnexpectedArgument {
+        directive: &'static str,
+        got: String,
+    },
+
+    #[error(transparent)]
+    Position(#[from] PositionError),
+
+    #[error("invalid direction `{got}`, expected one of `up`, `right`, `down` or `left`")]
+    Direction { got: String },
+
+    #[error("invalid count `{got}`")]
+    Count { got: String },
+
+    #[error(transparent)]
+    Cell(#[from] CellError),
+
+    #[error("invalid quoted string `{got}`")]
+    QuotedString { got: String },
+
+    #[error(
+        "invalid label `{got}`, expected ASCII letters, digits or `_`, not starting with a digit, \
+         and not a position"
+    )]
+    InvalidLabel { got: String },
+
+    #[error("label `{label}` is already defined")]
+    DuplicatedLabel { label: String },
+
+    #[error("label `{label}` is not defined")]
+    UndefinedLabel { label: String },
+
+    #[error("the head is already placed")]
+    DuplicatedHead,
+
+    #[error("the cursor went outside of the grid")]
+    OutOfGrid,
+
+    #[error("cell `{position}` is already placed")]
+    Overlap { position: Position },
+}
+
+/// The result of [`assemble()`]
+#[derive(Debug)]
+pub struct Assembly {
+    pub frame: Frame,
+
+    /// The position of every label
+    pub labels: BTreeMap<String, Position>,
+}
+
+/// Assemble a source into a [`Frame`], see the [module documentation](self) for the format
+///
+/// # Errors
+/// Returns an error, along with the line number (starting at 1), on the first line that could not
+/// be assembled. References to undefined labels are reported on the line of the reference
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    let mut assembler = Assembler::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+
+        assembler.line(line_number, line).map_err(|kind| AsmError {
+            line: line_number,
+            kind,
+        })?;
+    }
+
+    assembler.finish()
+}
+
+impl Frame {
+    /// Assemble a `Frame` from a source, see [`asm::assemble()`](assemble)
+    pub fn from_asm(source: &str) -> Result<Self, AsmError> {
+        assemble(source).map(|assembly| assembly.frame)
+    }
+}
+
+/// A reference to a label, resolved once every label is known
+struct Reference {
+    line: usize,
+    label: String,
+    kind: ReferenceKind,
+    slot: Slot,
+}
+
+#[derive(Clone, Copy)]
+enum ReferenceKind {
+    Address,
+    Pointer,
+}
+
+/// Where a resolved reference goes
+#[derive(Clone, Copy)]
+enum Slot {
+    Grid(Position),
+    Stack(usize),
+}
+
+/// A cell, as written in the source
+enum Item {
+    Content(Cell),
+    Reference(ReferenceKind, String),
+}
+
+struct Assembler {
+    frame: Frame,
+
+    /// Where the next cell is placed, `None` if the cursor went outside of the grid
+    cursor: Option<Position>,
+    direction: Direction,
+
+    labels: BTreeMap<String, Position>,
+    references: Vec<Reference>,
+    placed: HashSet<Position>,
+
+    /// Where the head starts, from a `.head` directive
+    head: Option<Head>,
+
+    /// Where the first cell was placed, the head starts there if no `.head` directive was given
+    first: Option<Head>,
+
+    stack: Vec<Operand>,
+}
+
+impl Assembler {
+    fn new() -> Self {
+        Self {
+            frame: Frame::default(),
+            cursor: Some(Position::ORIGIN),
+            direction: Direction::Right,
+            labels: BTreeMap::new(),
+            references: Vec::new(),
+            placed: HashSet::new(),
+            head: None,
+            first: None,
+            stack: Vec::new(),
+        }
+    }
+
+    fn line(&mut self, line_number: usize, line: &str) -> Result<(), AsmErrorKind> {
+        let mut rest = line.trim_start();
+
+        // Labels
+        while let Some(token) = next_token(rest)
+            && let Some(label) = token.strip_suffix(':')
+            && !token.starts_with('"')
+        {
+            self.label(label)?;
+            rest = rest[token.len()..].trim_start();
+        }
+
+        match next_token(rest) {
+            None => Ok(()),
+            Some(directive) if directive.starts_with('.') => {
+                let arguments = rest[directive.len()..].trim_start();
+                self.directive(line_number, &directive[1..], arguments)
+            }
+            Some(_) => {
+                for item in items(rest)? {
+                    let position = self.place()?;
+
+                    match item {
+                        Item::Content(cell) => self.frame.grid.set(position, cell),
+                        Item::Reference(kind, label) => self.references.push(Reference {
+                            line: line_number,
+                            label,
+                            kind,
+                            slot: Slot::Grid(position),
+                        }),
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn label(&mut self, label: &str) -> Result<(), AsmErrorKind> {
+        if !is_label(label) {
+            return Err(AsmErrorKind::InvalidLabel {
+                got: label.to_string(),
+            });
+        }
+
+        let position = self.cursor.ok_or(AsmErrorKind::OutOfGrid)?;
+        if self.labels.insert(label.to_string(), position).is_some() {
+            return Err(AsmErrorKind::DuplicatedLabel {
+                label: label.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reserve the cell under the cursor, and move the cursor to the next one
+    fn place(&mut self) -> Result<Position, AsmErrorKind> {
+        let position = self.cursor.ok_or(AsmErrorKind::OutOfGrid)?;
+
+        if !self.placed.insert(position) {
+            return Err(AsmErrorKind::Overlap { position });
+        }
+
+        self.first
+            .get_or_insert_with(|| Head::new(position, self.direction));
+
+        self.advance(1);
+
+        Ok(position)
+    }
+
+    fn advance(&mut self, count: u32) {
+        self.cursor = self
+            .cursor
+            .and_then(|cursor| cursor.checked_step(self.direction, count).ok());
+    }
+
+    fn directive(
+        &mut self,
+        line_number: usize,
+        directive: &str,
+        arguments: &str,
+    ) -> Result<(), AsmErrorKind> {
+        match directive {
+            "org" => {
+                let (position, rest) = next_argument(arguments, "org", "position")?;
+                no_more_arguments(rest, "org")?;
+
+                self.cursor = Some(position.parse()?);
+            }
+            "dir" => {
+                let (direction, rest) = next_argument(arguments, "dir", "direction")?;
+                no_more_arguments(rest, "dir")?;
+
+                self.direction = parse_direction(direction)?;
+            }
+            "turn" => {
+                let (direction, rest) = next_argument(arguments, "turn", "direction")?;
+                no_more_arguments(rest, "turn")?;
+
+                let direction = parse_direction(direction)?;
+                let opcode = match direction {
+                    Direction::Up => Opcode::Gup,
+                    Direction::Right => Opcode::Gri,
+                    Direction::Down => Opcode::Gdo,
+                    Direction::Left => Opcode::Gle,
+                };
+
+                let position = self.place()?;
+                self.frame
+                    .grid
+                    .set(position, Cell::new_trim(&opcode.to_string()));
+
+                // The head steps in the new direction, from the turning cell
+                self.direction = direction;
+                self.cursor = position.checked_step(direction, 1).ok();
+            }
+            "skip" => {
+                let count = if next_token(arguments).is_none() {
+                    1
+                } else {
+                    let (count, rest) = next_argument(arguments, "skip", "count")?;
+                    no_more_arguments(rest, "skip")?;
+
+                    count.parse().map_err(|_| AsmErrorKind::Count {
+                        got: count.to_string(),
+                    })?
+                };
+
+                self.advance(count);
+            }
+            "head" => {
+                no_more_arguments(arguments, "head")?;
+
+                if self.head.is_some() {
+                    return Err(AsmErrorKind::DuplicatedHead);
+                }
+
+                let position = self.cursor.ok_or(AsmErrorKind::OutOfGrid)?;
+                self.head = Some(Head::new(position, self.direction));
+            }
+            "stack" => {
+                for item in items(arguments)? {
+                    let operand = match item {
+                        Item::Content(cell) => Operand::from_cell(cell),
+                        Item::Reference(kind, label) => {
+                            self.references.push(Reference {
+                                line: line_number,
+                                label,
+                                kind,
+                                slot: Slot::Stack(self.stack.len()),
+                            });
+
+                            // Replaced once resolved
+                            Operand::from_cell(Cell::default())
+                        }
+                    };
+
+                    self.stack.push(operand);
+                }
+            }
+            _ => return Err(AsmErrorKind::UnknownDirective(directive.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Assembly, AsmError> {
+        for reference in self.references {
+            let position = *self.labels.get(&reference.label).ok_or_else(|| AsmError {
+                line: reference.line,
+                kind: AsmErrorKind::UndefinedLabel {
+                    label: reference.label.clone(),
+                },
+            })?;
+
+            let operand: Operand = match reference.kind {
+                ReferenceKind::Address => Address::from_position(position).into(),
+                ReferenceKind::Pointer => Pointer::from_position(position).into(),
+            };
+
+            match reference.slot {
+                Slot::Grid(at) => self.frame.grid.set(at, operand.to_cell()),
+                Slot::Stack(index) => self.stack[index] = operand,
+            }
+        }
+
+        self.frame.head = self.head.or(self.first).unwrap_or_default();
+        self.frame.stack = self.stack.into_iter().collect();
+
+        Ok(Assembly {
+            frame: self.frame,
+            labels: self.labels,
+        })
+    }
+}
+
+/// Returns `true` if `name` is a valid label name
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+        && !(name.len() == 2 && name.parse::<Position>().is_ok())
+}
+
+/// The next whitespace separated token of `line`, a `;` ends the token and starts a comment
+fn next_token(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+
+    if line.is_empty() || line.starts_with(';') {
+        None
+    } else {
+        line.split(|char: char| char.is_whitespace() || char == ';')
+            .next()
+    }
+}
+
+/// Split the next argument of `directive` from the rest of `arguments`, up to a comment
+fn next_argument<'a>(
+    arguments: &'a str,
+    directive: &'static str,
+    argument: &'static str,
+) -> Result<(&'a str, &'a str), ArgumentError> {
+    let arguments = arguments.trim_start();
+    let next = next_token(arguments).ok_or(ArgumentError::Missing {
+        directive,
+        argument,
+    })?;
+
+    Ok((next, arguments[next.len()..].trim_start()))
+}
+
+fn no_more_arguments(rest: &str, directive: &'static str) -> Result<(), ArgumentError> {
+    match next_token(rest) {
+        None => Ok(()),
+        Some(got) => Err(ArgumentError::Unexpected {
+            directive,
+            got: got.to_string(),
+        }),
+    }
+}
+
+/// Read every cell of `line`, up to the end of the line or a comment
+fn items(mut line: &str) -> Result<Vec<Item>, AsmErrorKind> {
+    let mut items = Vec::new();
+
+    loop {
+        line = line.trim_start();
+
+        if line.is_empty() || line.starts_with(';') {
+            break Ok(items);
+        }
+
+        if line.starts_with('"') {
+            let (content, rest) = unquote(line)?;
+            items.push(Item::Content(Cell::new(&content)?));
+            line = rest;
+        } else {
+            let token = next_token(line).expect("line is neither empty nor a comment");
+            line = &line[token.len()..];
+
+            let reference = token
+                .strip_prefix('@')
+                .map(|label| (ReferenceKind::Address, label))
+                .or_else(|| {
+                    token
+                        .strip_prefix('&')
+                        .map(|label| (ReferenceKind::Pointer, label))
+                });
+
+            match reference {
+                Some((kind, label)) if is_label(label) => {
+                    items.push(Item::Reference(kind, label.to_string()))
+                }
+                _ => items.push(Item::Content(Cell::new(token)?)),
+            }
+        }
+    }
+}
+
+impl From<ArgumentError> for AsmErrorKind {
+    fn from(value: ArgumentError) -> Self {
+        match value {
+            ArgumentError::Missing {
+                directive,
+                argument,
+            } => Self::MissingArgument {
+                directive,
+                argument,
+            },
+            ArgumentError::Unexpected { directive, got } => {
+                Self::UnexpectedArgument { directive, got }
+            }
+            ArgumentError::QuotedString { got } => Self::QuotedString { got },
+        }
+    }
+}
+
+fn parse_direction(direction: &str) -> Result<Direction, AsmErrorKind> {
+    direction.parse().map_err(|_| AsmErrorKind::Direction {
+        got: direction.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use act::State;
+
+    use super::*;
+    use crate::{FrameAction, lint};
+
+    fn as_json(frame: &Frame) -> serde_json::Value {
+        serde_json::to_value(frame).unwrap()
+    }
+
+    fn cell(frame: &Frame, position: &str) -> String {
+        frame.grid.get(position.parse().unwrap()).to_string()
+    }
+
+    fn error(source: &str) -> AsmError {
+        assemble(source).unwrap_err()
+    }
+
+    #[test]
+    fn countdown() {
+        let source = "
+            loop: @counter 1 sub @counter set
+                  @counter 0 neq @loop jif
+
+            .org AB
+            counter: 10
+        ";
+
+        let mut frame = Frame::from_asm(source).unwrap();
+        assert_eq!(
+            as_json(&frame),
+            as_json(&Frame::from_example("countdown").unwrap())
+        );
+        assert!(lint::lint(&frame).is_empty());
+
+        for _ in 0..200 {
+            let _ = frame.act(FrameAction::Step).unwrap();
+        }
+        assert_eq!(cell(&frame, "AB"), "0");
+    }
+
+    #[test]
+    fn layout() {
+        let assembly = assemble(
+            "
+            .org BB
+            .stack 1 @data &data \"a b\"
+            start: .turn down ; the head starts on BB, going right
+            @data jmp
+            .skip 2
+            .dir left
+            x y
+            .org AE
+            .head
+            data: 5
+            ",
+        )
+        .unwrap();
+
+        let frame = &assembly.frame;
+        assert_eq!(cell(frame, "BB"), "gdo");
+        assert_eq!(cell(frame, "BC"), "@AE");
+        assert_eq!(cell(frame, "BD"), "jmp");
+        assert!(frame.grid.get("BE".parse().unwrap()).is_empty());
+        assert!(frame.grid.get("BF".parse().unwrap()).is_empty());
+        assert_eq!(cell(frame, "BG"), "x");
+        assert_eq!(cell(frame, "AG"), "y");
+        assert_eq!(cell(frame, "AE"), "5");
+
+        assert_eq!(frame.head.position, "AE".parse().unwrap());
+        assert_eq!(frame.head.direction, Direction::Left);
+        assert_eq!(
+            frame
+                .stack
+                .iter()
+                .map(Operand::to_string)
+                .collect::<Vec<_>>(),
+            ["1", "@AE", "&AE", "a b"]
+        );
+
+        assert_eq!(assembly.labels["start"], "BB".parse().unwrap());
+        assert_eq!(assembly.labels["data"], "AE".parse().unwrap());
+    }
+
+    #[test]
+    fn comments() {
+        let frame = Frame::from_asm(
+            "
+            .stack \";\" \"a;b\" 1;comment
+            a \";\";comment
+            .skip;comment
+            .org AB;comment
+            b; comment
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            frame
+                .stack
+                .iter()
+                .map(Operand::to_string)
+                .collect::<Vec<_>>(),
+            [";", "a;b", "1"]
+        );
+        assert_eq!(cell(&frame, "AA"), "a");
+        assert_eq!(cell(&frame, "BA"), ";");
+        assert!(frame.grid.get("CA".parse().unwrap()).is_empty());
+        assert_eq!(cell(&frame, "AB"), "b");
+    }
+
+    #[test]
+    fn default_head() {
+        let frame = Frame::from_asm(".dir down\n.org CC\n1 2").unwrap();
+        assert_eq!(frame.head.position, "CC".parse().unwrap());
+        assert_eq!(frame.head.direction, Direction::Down);
+
+        // Positions are not labels, they are kept as is
+        let frame = Frame::from_asm("@AB &ZZ @+A").unwrap();
+        assert_eq!(cell(&frame, "AA"), "@AB");
+        assert_eq!(cell(&frame, "BA"), "&ZZ");
+        assert_eq!(cell(&frame, "CA"), "@+A");
+    }
+
+    #[test]
+    fn errors() {
+        let err = |line, kind| AsmError { line, kind };
+
+        assert_eq!(
+            error("a\n\n@nowhere"),
+            err(
+                3,
+                AsmErrorKind::UndefinedLabel {
+                    label: "nowhere".into()
+                }
+            )
+        );
+        assert_eq!(
+            error("a: 1\na: 2"),
+            err(2, AsmErrorKind::DuplicatedLabel { label: "a".into() })
+        );
+        assert_eq!(
+            error("AB: 1"),
+            err(1, AsmErrorKind::InvalidLabel { got: "AB".into() })
+        );
+        assert_eq!(
+            error("1 2\n.org BA\n3"),
+            err(
+                3,
+                AsmErrorKind::Overlap {
+                    position: "BA".parse().unwrap()
+                }
+            )
+        );
+        assert_eq!(error(".dir left\n1 2"), err(2, AsmErrorKind::OutOfGrid));
+        assert_eq!(
+            error(".dir sideways"),
+            err(
+                1,
+                AsmErrorKind::Direction {
+                    got: "sideways".into()
+                }
+            )
+        );
+        assert_eq!(
+            error(".org"),
+            err(
+                1,
+                AsmErrorKind::MissingArgument {
+                    directive: "org",
+                    argument: "position"
+                }
+            )
+        );
+        assert_eq!(
+            error(".org AA BB"),
+            err(
+                1,
+                AsmErrorKind::UnexpectedArgument {
+                    directive: "org",
+                    got: "BB".into()
+                }
+            )
+        );
+        assert_eq!(error(".head\n.head"), err(2, AsmErrorKind::DuplicatedHead));
+        assert_eq!(
+            error(".jump"),
+            err(1, AsmErrorKind::UnknownDirective("jump".into()))
+        );
+        assert!(matches!(error("abcd").kind, AsmErrorKind::Cell(_)));
+        assert!(matches!(
+            error("\"abc").kind,
+            AsmErrorKind::QuotedString { .. }
+        ));
+    }
+}
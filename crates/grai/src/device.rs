@@ -0,0 +1,62 @@
+//! Devices a program drives through [host functions](crate::HostFunctions)
+//!
+//! A [`Device`] is shared between the application, which reads what the program produced, and
+//! the host functions it registers on a [`Frame`](crate::Frame), called by the program with
+//! `cal`. Devices are attached with [`Device::attach()`], or with
+//! [`InterpreterBuilder::device()`](crate::InterpreterBuilder::device).
+//!
+//! # Examples
+//! ```
+//! # use std::sync::{Arc, Mutex};
+//! # use grai::{Interpreter, Outcome, device::{Color, Framebuffer}};
+//! let framebuffer = Arc::new(Mutex::new(Framebuffer::new(4, 4)));
+//!
+//! // Paint the pixel at 1, 2 in orange, and present the frame
+//! let mut interpreter = Interpreter::builder()
+//!     .text("A |1  |2  |f80|pix|cal|shw|cal|")
+//!     .device(&framebuffer)
+//!     .max_steps(7)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(interpreter.run().unwrap(), Outcome::StepLimit);
+//!
+//! let framebuffer = framebuffer.lock().unwrap();
+//! let image = &framebuffer.frames()[0];
+//! assert_eq!(image.pixel(1, 2), Some(Color::new(0xff, 0x88, 0x00)));
+//!
+//! let mut png = Vec::new();
+//! image.write_png(&mut png).unwrap();
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use crate::{HostFunctions, Operand};
+
+mod framebuffer;
+pub use framebuffer::*;
+
+mod speaker;
+pub use speaker::*;
+
+/// Something a program drives through host functions, see the [module documentation](self)
+pub trait Device: Send + 'static {
+    /// Register the host functions driving `device` in `functions`
+    fn attach(device: &Arc<Mutex<Self>>, functions: &mut HostFunctions)
+    where
+        Self: Sized;
+}
+
+/// Read the argument of a host function as a number
+pub(crate) fn number(argument: &Operand) -> Result<u32, String> {
+    match argument {
+        Operand::Literal(literal) => literal.try_as_number().map_err(|err| err.to_string()),
+        _ => Err(format!("expected a number, got `{argument}`")),
+    }
+}
+
+/// Lock a device from one of its host functions
+pub(crate) fn lock<T>(device: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    device
+        .lock()
+        .expect("the device lock should not be poisoned")
+}
@@ -0,0 +1,410 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use crate::{HostFunctions, Operand};
+
+use super::{Device, lock, number};
+
+/// A color, written in cells as three hexadecimal digits, `rgb`, each digit standing for one
+/// channel, as in CSS
+///
+/// # Examples
+/// ```
+/// # use grai::device::Color;
+/// let orange: Color = "f80".parse().unwrap();
+/// assert_eq!(orange, Color::new(0xff, 0x88, 0x00));
+/// assert_eq!(orange.to_string(), "f80");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::new(0, 0, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("invalid color `{0}`, expected three hexadecimal digits such as `f80`")]
+pub struct ColorError(String);
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let channel = |char: char| {
+            char.to_digit(16)
+                .map(|digit| digit as u8 * 0x11)
+                .ok_or_else(|| ColorError(s.to_string()))
+        };
+
+        match s.chars().collect::<Vec<_>>()[..] {
+            [r, g, b] => Ok(Self::new(channel(r)?, channel(g)?, channel(b)?)),
+            _ => Err(ColorError(s.to_string())),
+        }
+    }
+}
+
+impl Display for Color {
+    /// Channels are rounded to the nearest digit
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digit = |channel: u8| (channel as u32 + 8) / 0x11;
+        write!(
+            f,
+            "{:x}{:x}{:x}",
+            digit(self.r),
+            digit(self.g),
+            digit(self.b)
+        )
+    }
+}
+
+/// An image of a [`Framebuffer`], row after row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+/// Formats an [`Image`] can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Binary portable pixmap, `P6`
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ppm => "ppm",
+            Self::Png => "png",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("could not encode png : {0}")]
+    Png(#[from] png::EncodingError),
+}
+
+impl Image {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The color of the pixel at `x`, `y`, `None` if outside of the image
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        self.index(x, y).map(|index| self.pixels[index])
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| (y * self.width + x) as usize)
+    }
+
+    /// Every channel of every pixel, row after row
+    fn rgb(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect()
+    }
+
+    pub fn write_ppm(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.rgb())
+    }
+
+    pub fn write_png(&self, writer: impl Write) -> Result<(), ImageError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgb())?;
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    pub fn write(&self, writer: impl Write, format: ImageFormat) -> Result<(), ImageError> {
+        match format {
+            ImageFormat::Ppm => Ok(self.write_ppm(writer)?),
+            ImageFormat::Png => self.write_png(writer),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: ImageFormat) -> Result<(), ImageError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, format)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// A graphical output [`Device`], an image programs draw on, and present frame after frame
+///
+/// # Host functions
+/// - `pix` takes `x`, `y` and a [`Color`], and paints the pixel at `x`, `y`
+/// - `cls` takes a [`Color`], and paints every pixel
+/// - `shw` takes no argument, and presents the image, adding it to the
+///   [frames](Framebuffer::frames)
+///
+/// See the [module documentation](super) for an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+    image: Image,
+    frames: Vec<Image>,
+}
+
+impl Framebuffer {
+    /// Obtain a black `Framebuffer` of `width` by `height` pixels
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            image: Image {
+                width,
+                height,
+                pixels: vec![Color::BLACK; width as usize * height as usize],
+            },
+            frames: Vec::new(),
+        }
+    }
+
+    /// The image being drawn on
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Paint the pixel at `x`, `y`, returns `false` if it is outside of the framebuffer
+    pub fn set(&mut self, x: u32, y: u32, color: Color) -> bool {
+        match self.image.index(x, y) {
+            Some(index) => {
+                self.image.pixels[index] = color;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Paint every pixel
+    pub fn clear(&mut self, color: Color) {
+        self.image.pixels.fill(color);
+    }
+
+    /// Add the image to the frames
+    pub fn present(&mut self) {
+        self.frames.push(self.image.clone());
+    }
+
+    /// Every presented image, in order
+    pub fn frames(&self) -> &[Image] {
+        &self.frames
+    }
+
+    /// Forget the presented images, returning them
+    pub fn take_frames(&mut self) -> Vec<Image> {
+        std::mem::take(&mut self.frames)
+    }
+
+    /// Save every presented image in `directory`, as `frame_0000`, `frame_0001`… with the
+    /// extension of `format`, returns the paths of the saved images
+    ///
+    /// # Errors
+    /// Returns an error if an image could not be saved, stopping there
+    pub fn save_frames(
+        &self,
+        directory: impl AsRef<Path>,
+        format: ImageFormat,
+    ) -> Result<Vec<PathBuf>, ImageError> {
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let path = directory
+                    .as_ref()
+                    .join(format!("frame_{index:04}.{}", format.extension()));
+                image.save(&path, format)?;
+
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+fn color(argument: &Operand) -> Result<Color, String> {
+    match argument {
+        Operand::Literal(literal) => literal
+            .as_str()
+            .parse()
+            .map_err(|err: ColorError| err.to_string()),
+        _ => Err(ColorError(argument.to_string()).to_string()),
+    }
+}
+
+impl Device for Framebuffer {
+    fn attach(device: &Arc<Mutex<Self>>, functions: &mut HostFunctions) {
+        let framebuffer = Arc::clone(device);
+        functions
+            .register("pix", 3, move |args| {
+                let (x, y, color) = (number(&args[0])?, number(&args[1])?, color(&args[2])?);
+                let mut framebuffer = lock(&framebuffer);
+
+                if framebuffer.set(x, y, color) {
+                    Ok(Vec::new())
+                } else {
+                    let image = framebuffer.image();
+                    Err(format!(
+                        "pixel {x}, {y} is outside of the {}x{} framebuffer",
+                        image.width(),
+                        image.height()
+                    ))
+                }
+            })
+            .expect("`pix` should be a valid function name");
+
+        let framebuffer = Arc::clone(device);
+        functions
+            .register("cls", 1, move |args| {
+                lock(&framebuffer).clear(color(&args[0])?);
+                Ok(Vec::new())
+            })
+            .expect("`cls` should be a valid function name");
+
+        let framebuffer = Arc::clone(device);
+        functions
+            .register("shw", 0, move |_| {
+                lock(&framebuffer).present();
+                Ok(Vec::new())
+            })
+            .expect("`shw` should be a valid function name");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CallError, EvaluationError, Frame, FrameError};
+
+    fn run(text: &str, framebuffer: &Arc<Mutex<Framebuffer>>) -> Result<(), FrameError> {
+        let mut frame = Frame::from_text(text).unwrap();
+        Framebuffer::attach(framebuffer, &mut frame.functions);
+
+        while frame.grid.word(frame.head.position).is_some() {
+            let _ = frame.step()?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!("FFF".parse(), Ok(Color::new(0xff, 0xff, 0xff)));
+        assert_eq!(Color::new(0x10, 0x80, 0xf7).to_string(), "18f");
+        assert!("ff".parse::<Color>().is_err());
+        assert!("ffg".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn draw() {
+        let framebuffer = Arc::new(Mutex::new(Framebuffer::new(2, 2)));
+        run(
+            "A |00f|cls|cal|1  |0  |f00|pix|cal|shw|cal|1  |1  |0f0|pix|cal|shw|cal|",
+            &framebuffer,
+        )
+        .unwrap();
+
+        let framebuffer = framebuffer.lock().unwrap();
+        let [first, second] = framebuffer.frames() else {
+            panic!("expected two frames");
+        };
+
+        let blue = Color::new(0, 0, 0xff);
+        let red = Color::new(0xff, 0, 0);
+        assert_eq!(first.pixel(0, 0), Some(blue));
+        assert_eq!(first.pixel(1, 0), Some(red));
+        assert_eq!(first.pixel(2, 0), None);
+
+        // Presented frames are not changed by later drawing
+        assert_eq!(first.pixel(1, 1), Some(blue));
+        assert_eq!(second.pixel(1, 1), Some(Color::new(0, 0xff, 0)));
+
+        let mut ppm = Vec::new();
+        first.write_ppm(&mut ppm).unwrap();
+        assert_eq!(
+            ppm,
+            [
+                b"P6\n2 2\n255\n".as_slice(),
+                &[0, 0, 0xff, 0xff, 0, 0],
+                &[0, 0, 0xff, 0, 0, 0xff],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let framebuffer = Arc::new(Mutex::new(Framebuffer::new(2, 2)));
+
+        let error = run("A |2  |0  |fff|pix|cal|", &framebuffer).unwrap_err();
+        let FrameError::Evaluation(EvaluationError::Call(CallError::Failed { message, .. })) =
+            error
+        else {
+            panic!("expected a failed call, got {error:?}");
+        };
+        assert_eq!(message, "pixel 2, 0 is outside of the 2x2 framebuffer");
+
+        assert!(run("A |xyz|cls|cal|", &framebuffer).is_err());
+    }
+
+    #[test]
+    fn export() {
+        let mut framebuffer = Framebuffer::new(3, 1);
+        framebuffer.set(2, 0, Color::new(0x11, 0x22, 0x33));
+        framebuffer.present();
+        framebuffer.clear(Color::new(0xff, 0xff, 0xff));
+        framebuffer.present();
+
+        let directory = std::env::temp_dir().join(format!("grai-frames-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let paths = framebuffer
+            .save_frames(&directory, ImageFormat::Png)
+            .unwrap();
+        assert_eq!(paths[1], directory.join("frame_0001.png"));
+
+        let decoder = png::Decoder::new(io::BufReader::new(File::open(&paths[0]).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut data).unwrap();
+
+        assert_eq!((info.width, info.height), (3, 1));
+        assert_eq!(
+            &data[..info.buffer_size()],
+            [0, 0, 0, 0, 0, 0, 0x11, 0x22, 0x33]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
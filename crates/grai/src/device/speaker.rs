@@ -0,0 +1,338 @@
+use std::{
+    f64::consts::TAU,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{HostFunctions, Operand};
+
+use super::{Device, lock, number};
+
+/// A sound queued on a [`Speaker`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sound {
+    /// A tone of `frequency` hertz, lasting `duration` milliseconds, `volume` being a percentage
+    Tone {
+        frequency: f64,
+        duration: u32,
+        volume: u8,
+    },
+
+    /// A raw 8-bit unsigned sample, `128` being silence
+    Sample(u8),
+}
+
+/// The shape of the wave of tones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Square,
+}
+
+/// An audio output [`Device`], queuing sounds one after the other, and rendering them to a WAV
+/// file, offline
+///
+/// # Host functions
+/// - `ton` takes a frequency in hertz, a duration in milliseconds and a volume in percent, and
+///   queues a tone
+/// - `nte` takes a MIDI note number (`69` being A4, at 440 Hz), a duration and a volume, and
+///   queues the tone of that note
+/// - `smp` takes a number from `0` to `255`, and queues it as a raw sample, `128` being silence
+///
+/// # Examples
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use grai::{Interpreter, Outcome, device::Speaker};
+/// let speaker = Arc::new(Mutex::new(Speaker::new(8000)));
+///
+/// // A4 for a quarter of a second, at half the volume
+/// let mut interpreter = Interpreter::builder()
+///     .text("A |69 |250|50 |nte|cal|")
+///     .device(&speaker)
+///     .max_steps(5)
+///     .build()
+///     .unwrap();
+/// assert_eq!(interpreter.run().unwrap(), Outcome::StepLimit);
+///
+/// let speaker = speaker.lock().unwrap();
+/// assert_eq!(speaker.render().len(), 2000);
+///
+/// let mut wav = Vec::new();
+/// speaker.write_wav(&mut wav).unwrap();
+/// assert_eq!(&wav[..4], b"RIFF");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speaker {
+    sample_rate: u32,
+    waveform: Waveform,
+    sounds: Vec<Sound>,
+}
+
+impl Speaker {
+    /// Obtain a `Speaker` rendering `sample_rate` samples per second, of [sine](Waveform::Sine)
+    /// waves
+    pub fn new(sample_rate: u32) -> Self {
+        Self::with_waveform(sample_rate, Waveform::default())
+    }
+
+    pub fn with_waveform(sample_rate: u32, waveform: Waveform) -> Self {
+        Self {
+            sample_rate,
+            waveform,
+            sounds: Vec::new(),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Every queued sound, in order
+    pub fn sounds(&self) -> &[Sound] {
+        &self.sounds
+    }
+
+    /// Forget every queued sound
+    pub fn clear(&mut self) {
+        self.sounds.clear();
+    }
+
+    /// Queue a tone, `volume` is clamped to 100 percent
+    pub fn tone(&mut self, frequency: f64, duration: u32, volume: u8) {
+        self.sounds.push(Sound::Tone {
+            frequency,
+            duration,
+            volume: volume.min(100),
+        });
+    }
+
+    /// Queue the tone of a MIDI `note`
+    pub fn note(&mut self, note: u8, duration: u32, volume: u8) {
+        let frequency = 440.0 * 2f64.powf((note as f64 - 69.0) / 12.0);
+        self.tone(frequency, duration, volume);
+    }
+
+    /// Queue a raw sample
+    pub fn sample(&mut self, sample: u8) {
+        self.sounds.push(Sound::Sample(sample));
+    }
+
+    /// Render every queued sound, as signed 16-bit samples
+    pub fn render(&self) -> Vec<i16> {
+        let mut samples = Vec::new();
+
+        for sound in &self.sounds {
+            match *sound {
+                Sound::Tone {
+                    frequency,
+                    duration,
+                    volume,
+                } => {
+                    let count = self.sample_rate as u64 * duration as u64 / 1000;
+                    let amplitude = i16::MAX as f64 * volume as f64 / 100.0;
+
+                    samples.extend((0..count).map(|index| {
+                        let phase = (frequency * index as f64 / self.sample_rate as f64).fract();
+                        let wave = match self.waveform {
+                            Waveform::Sine => (phase * TAU).sin(),
+                            Waveform::Square if phase < 0.5 => 1.0,
+                            Waveform::Square => -1.0,
+                        };
+
+                        (wave * amplitude).round() as i16
+                    }));
+                }
+                Sound::Sample(sample) => samples.push((sample as i16 - 128) * 256),
+            }
+        }
+
+        samples
+    }
+
+    /// Write the [rendered](Speaker::render) sounds as a mono, 16-bit PCM, WAV file
+    pub fn write_wav(&self, mut writer: impl Write) -> io::Result<()> {
+        let samples = self.render();
+        let data_size = samples.len() as u32 * 2;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&1u16.to_le_bytes())?; // Mono
+        writer.write_all(&self.sample_rate.to_le_bytes())?;
+        writer.write_all(&(self.sample_rate * 2).to_le_bytes())?; // Bytes per second
+        writer.write_all(&2u16.to_le_bytes())?; // Bytes per sample
+        writer.write_all(&16u16.to_le_bytes())?; // Bits per sample
+
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())?;
+        for sample in samples {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_wav(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_wav(&mut writer)?;
+        writer.flush()
+    }
+}
+
+/// Read the argument of a host function as a number, up to `max`
+fn bounded(argument: &Operand, name: &str, max: u32) -> Result<u32, String> {
+    let value = number(argument)?;
+    if value > max {
+        return Err(format!("{name} should be at most {max}, got {value}"));
+    }
+
+    Ok(value)
+}
+
+impl Device for Speaker {
+    fn attach(device: &Arc<Mutex<Self>>, functions: &mut HostFunctions) {
+        let speaker = Arc::clone(device);
+        functions
+            .register("ton", 3, move |args| {
+                let frequency = number(&args[0])?;
+                let duration = number(&args[1])?;
+                let volume = bounded(&args[2], "volume", 100)?;
+
+                lock(&speaker).tone(frequency as f64, duration, volume as u8);
+                Ok(Vec::new())
+            })
+            .expect("`ton` should be a valid function name");
+
+        let speaker = Arc::clone(device);
+        functions
+            .register("nte", 3, move |args| {
+                let note = bounded(&args[0], "note", 127)?;
+                let duration = number(&args[1])?;
+                let volume = bounded(&args[2], "volume", 100)?;
+
+                lock(&speaker).note(note as u8, duration, volume as u8);
+                Ok(Vec::new())
+            })
+            .expect("`nte` should be a valid function name");
+
+        let speaker = Arc::clone(device);
+        functions
+            .register("smp", 1, move |args| {
+                let sample = bounded(&args[0], "sample", 255)?;
+
+                lock(&speaker).sample(sample as u8);
+                Ok(Vec::new())
+            })
+            .expect("`smp` should be a valid function name");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CallError, EvaluationError, Frame, FrameError};
+
+    fn run(text: &str, speaker: &Arc<Mutex<Speaker>>) -> Result<(), FrameError> {
+        let mut frame = Frame::from_text(text).unwrap();
+        Speaker::attach(speaker, &mut frame.functions);
+
+        while frame.grid.word(frame.head.position).is_some() {
+            let _ = frame.step()?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn queue() {
+        let speaker = Arc::new(Mutex::new(Speaker::new(1000)));
+        run(
+            "A |500|10 |100|ton|cal|81 |5  |0  |nte|cal|255|smp|cal|0  |smp|cal|",
+            &speaker,
+        )
+        .unwrap();
+
+        let speaker = speaker.lock().unwrap();
+        assert_eq!(
+            speaker.sounds(),
+            [
+                Sound::Tone {
+                    frequency: 500.0,
+                    duration: 10,
+                    volume: 100
+                },
+                Sound::Tone {
+                    frequency: 880.0,
+                    duration: 5,
+                    volume: 0
+                },
+                Sound::Sample(255),
+                Sound::Sample(0),
+            ]
+        );
+
+        let samples = speaker.render();
+        assert_eq!(samples.len(), 10 + 5 + 2);
+
+        // At 500 Hz, every sample falls on a zero of the sine
+        assert_eq!(samples[0], 0);
+        assert!(samples[..10].iter().all(|sample| sample.abs() < 10));
+        assert!(samples[10..15].iter().all(|&sample| sample == 0));
+        assert_eq!(samples[15..], [127 * 256, -128 * 256]);
+    }
+
+    #[test]
+    fn square() {
+        let mut speaker = Speaker::with_waveform(8, Waveform::Square);
+        speaker.tone(2.0, 1000, 50);
+
+        let high = (i16::MAX as f64 / 2.0).round() as i16;
+        assert_eq!(
+            speaker.render(),
+            [high, high, -high, -high, high, high, -high, -high]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let speaker = Arc::new(Mutex::new(Speaker::new(1000)));
+
+        let error = run("A |440|10 |101|ton|cal|", &speaker).unwrap_err();
+        let FrameError::Evaluation(EvaluationError::Call(CallError::Failed { message, .. })) =
+            error
+        else {
+            panic!("expected a failed call, got {error:?}");
+        };
+        assert_eq!(message, "volume should be at most 100, got 101");
+
+        assert!(run("A |128|10 |50 |nte|cal|", &speaker).is_err());
+        assert!(run("A |256|smp|cal|", &speaker).is_err());
+        assert!(speaker.lock().unwrap().sounds().is_empty());
+    }
+
+    #[test]
+    fn wav() {
+        let mut speaker = Speaker::new(8000);
+        speaker.sample(128);
+        speaker.sample(129);
+
+        let mut wav = Vec::new();
+        speaker.write_wav(&mut wav).unwrap();
+
+        assert_eq!(wav.len(), 44 + 4);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav[4..8], 40u32.to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(wav[24..28], 8000u32.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav[40..44], 4u32.to_le_bytes());
+        assert_eq!(wav[44..], [0, 0, 0, 1]);
+    }
+}
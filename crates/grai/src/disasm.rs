@@ -0,0 +1,323 @@
+//! Annotated listing of a [`Frame`]
+//!
+//! A [`Listing`] follows the paths the head can take, run by run (see [`flow`](crate::flow)),
+//! and shows every cell along the way with the [`Word`] it decodes to and, for addresses and
+//! pointers, what they resolve to. Cells the head never reaches, such as data, are listed at the
+//! end. It is meant to be read by humans, in bug reports or code reviews, rather than raw JSON.
+//!
+//! # Examples
+//! ```
+//! # use grai::{Frame, disasm::Listing};
+//! let frame = Frame::from_example("countdown").unwrap();
+//! let listing = Listing::new(&frame);
+//!
+//! let text = listing.to_string();
+//! assert!(text.starts_with("run 0 : AA right\n"));
+//! assert!(text.contains("  AA  @AB  address  -> AB: 10\n"));
+//! ```
+//!
+//! ```text
+//! run 0 : AA right
+//!   AA  @AB  address  -> AB: 10
+//!   BA  1    literal
+//!   CA  sub  opcode
+//!   …
+//!   JA  jif  opcode
+//!   => run 1
+//!   => run 0 (jump)
+//! ```
+
+use std::{collections::HashSet, fmt::Display};
+
+use crate::{
+    Cell, Direction, Frame, Operand, Position, Word,
+    flow::{ControlFlowGraph, EdgeKind, Target},
+};
+
+/// A cell of a [`Listing`], along with what it decodes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub position: Position,
+    pub content: Cell,
+    pub word: Word,
+
+    /// What an address or a pointer resolves to, `None` for other words
+    pub resolution: Option<Resolution>,
+}
+
+/// The cells read when resolving an address or a pointer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    /// Every cell read, in order, with its content. An address reads a single cell, a pointer
+    /// reads cells until one does not hold a pointer
+    pub hops: Vec<(Position, Cell)>,
+
+    /// `true` if the pointers loop, the resolution never ends
+    pub looping: bool,
+}
+
+/// A [`Run`](crate::flow::Run) of a [`Listing`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingRun {
+    pub entry: Position,
+    pub direction: Direction,
+
+    /// Non empty cells of the run
+    pub entries: Vec<Entry>,
+
+    /// Where the head goes after the run
+    pub next: Vec<(Target, EdgeKind)>,
+}
+
+/// Annotated listing of a [`Frame`], see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+    /// Runs in the order of [`ControlFlowGraph::runs`], the first is the one the head starts in
+    pub runs: Vec<ListingRun>,
+
+    /// Non empty cells the head never reaches, ordered by row then column
+    pub unreached: Vec<Entry>,
+}
+
+impl Listing {
+    pub fn new(frame: &Frame) -> Self {
+        let graph = ControlFlowGraph::new(frame);
+        let mut reached = HashSet::new();
+
+        let runs = graph
+            .runs
+            .iter()
+            .enumerate()
+            .map(|(id, run)| ListingRun {
+                entry: run.entry(),
+                direction: run.direction,
+                entries: run
+                    .cells
+                    .iter()
+                    .filter(|cell| !cell.content.is_empty())
+                    .map(|cell| {
+                        reached.insert(cell.position);
+                        Entry::new(frame, cell.position)
+                    })
+                    .collect(),
+                next: graph
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.from == id)
+                    .map(|edge| (edge.to, edge.kind))
+                    .collect(),
+            })
+            .collect();
+
+        let unreached = frame
+            .grid
+            .cells()
+            .filter(|(position, _)| !reached.contains(position))
+            .map(|(position, _)| Entry::new(frame, position))
+            .collect();
+
+        Self { runs, unreached }
+    }
+}
+
+impl Entry {
+    fn new(frame: &Frame, position: Position) -> Self {
+        let content = frame.grid.get(position);
+        let word = Word::from_cell(content.clone());
+        let word = frame.opcodes.named_by(&word).map_or(word, Word::Custom);
+
+        let resolution = match &word {
+            Word::Operand(Operand::Address(address)) => {
+                let position = *address.position();
+                Some(Resolution {
+                    hops: vec![(position, frame.grid.get(position))],
+                    looping: false,
+                })
+            }
+            Word::Operand(Operand::Pointer(pointer)) => {
+                let mut hops: Vec<(Position, Cell)> = Vec::new();
+                let mut next = *pointer.position();
+
+                let looping = loop {
+                    if hops.iter().any(|(position, _)| *position == next) {
+                        break true;
+                    }
+
+                    let cell = frame.grid.get(next);
+                    hops.push((next, cell.clone()));
+
+                    match Operand::from_cell(cell) {
+                        Operand::Pointer(pointer) => next = *pointer.position(),
+                        _ => break false,
+                    }
+                };
+
+                Some(Resolution { hops, looping })
+            }
+            Word::Operand(_) | Word::Opcode(_) | Word::Custom(_) => None,
+        };
+
+        Self {
+            position,
+            content,
+            word,
+            resolution,
+        }
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match &self.word {
+            Word::Opcode(_) | Word::Custom(_) => "opcode".to_string(),
+            Word::Operand(operand) => operand.kind().to_string(),
+        };
+
+        let line = format!("{}  {:<3}  {kind:<7}", self.position, self.content.as_str());
+        match &self.resolution {
+            None => f.write_str(line.trim_end()),
+            Some(resolution) => write!(f, "{line}  {resolution}"),
+        }
+    }
+}
+
+impl Display for Resolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, (position, content)) in self.hops.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+
+            if content.is_empty() {
+                write!(f, "-> {position}: (empty)")?;
+            } else {
+                write!(f, "-> {position}: {content}")?;
+            }
+        }
+
+        if self.looping {
+            f.write_str(" -> (loop)")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (id, run) in self.runs.iter().enumerate() {
+            if id > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "run {id} : {} {}", run.entry, run.direction)?;
+            for entry in &run.entries {
+                writeln!(f, "  {entry}")?;
+            }
+
+            for (target, kind) in &run.next {
+                let target = match target {
+                    Target::Run(id) => format!("run {id}"),
+                    Target::Halt => "halt".to_string(),
+                    Target::Error => "error".to_string(),
+                    Target::Unknown => "unknown".to_string(),
+                };
+
+                match kind {
+                    EdgeKind::Jump => writeln!(f, "  => {target} (jump)")?,
+                    EdgeKind::Step | EdgeKind::Stop => writeln!(f, "  => {target}")?,
+                }
+            }
+        }
+
+        if !self.unreached.is_empty() {
+            if !self.runs.is_empty() {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "not reached")?;
+            for entry in &self.unreached {
+                writeln!(f, "  {entry}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Opcode;
+
+    #[test]
+    fn countdown() {
+        let listing = Listing::new(&Frame::from_example("countdown").unwrap());
+
+        assert_eq!(
+            listing.to_string(),
+            "run 0 : AA right\n\
+             \x20 AA  @AB  address  -> AB: 10\n\
+             \x20 BA  1    literal\n\
+             \x20 CA  sub  opcode\n\
+             \x20 DA  @AB  address  -> AB: 10\n\
+             \x20 EA  set  opcode\n\
+             \x20 FA  @AB  address  -> AB: 10\n\
+             \x20 GA  0    literal\n\
+             \x20 HA  neq  opcode\n\
+             \x20 IA  @AA  address  -> AA: @AB\n\
+             \x20 JA  jif  opcode\n\
+             \x20 => run 1\n\
+             \x20 => run 0 (jump)\n\
+             \n\
+             run 1 : KA right\n\
+             \x20 => halt\n\
+             \n\
+             not reached\n\
+             \x20 AB  10   literal\n"
+        );
+    }
+
+    #[test]
+    fn words() {
+        let frame = Frame::from_text(
+            "head AA right\n\
+             A |&BB|&AC|###|gri|\n\
+             B |   |&CB|&BB|\n\
+             C |&AC|\n",
+        )
+        .unwrap();
+        let listing = Listing::new(&frame);
+
+        let entries = &listing.runs[0].entries;
+        assert_eq!(
+            entries[0].word,
+            Word::Operand(Operand::from_str("&BB").unwrap())
+        );
+        assert_eq!(
+            entries[2].word,
+            Word::Operand(Operand::from_str("###").unwrap())
+        );
+        assert_eq!(entries[3].word, Word::Opcode(Opcode::Gri));
+        assert_eq!(entries[2].resolution, None);
+
+        let resolution = |position: &str| {
+            let position = position.parse().unwrap();
+            listing
+                .runs
+                .iter()
+                .flat_map(|run| &run.entries)
+                .chain(&listing.unreached)
+                .find(|entry| entry.position == position)
+                .unwrap()
+                .resolution
+                .as_ref()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(resolution("AA"), "-> BB: &CB -> CB: &BB -> (loop)");
+        assert_eq!(resolution("BA"), "-> AC: &AC -> (loop)");
+        assert_eq!(resolution("CB"), "-> BB: &CB -> CB: &BB -> (loop)");
+    }
+}
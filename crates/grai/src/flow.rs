@@ -0,0 +1,518 @@
+//! Control flow graph of a [`Frame`]
+//!
+//! The head is walked from its starting position through every path it could take, while keeping
+//! track of what is known of the stack. It follows direction changes, and `jmp` / `jif` whose
+//! address is known statically, a `jif` on a value computed at runtime exploring both branches.
+//! The cells it goes through are then grouped in [`Run`]s : sequences of cells the head always
+//! goes through one after the other, that are only entered by their first cell.
+//!
+//! A [`ControlFlowGraph`] can be exported as JSON, or as [Graphviz](https://graphviz.org) DOT to be
+//! visualised.
+//!
+//! # Examples
+//! ```
+//! # use grai::{Frame, flow::{ControlFlowGraph, Target}};
+//! let frame = Frame::from_example("countdown").unwrap();
+//! let graph = ControlFlowGraph::new(&frame);
+//!
+//! // The loop, and the cells after it, up to the edge of the grid
+//! assert_eq!(graph.runs.len(), 2);
+//! assert!(graph.edges.iter().any(|edge| edge.from == 0 && edge.to == Target::Run(0)));
+//!
+//! let dot = graph.to_dot();
+//! assert!(dot.starts_with("digraph"));
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Write,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Cell, Direction, Frame, Position,
+    analysis::{self, Flow, HeadKey, Stop},
+};
+
+/// A cell of a [`Run`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCell {
+    pub position: Position,
+    pub content: Cell,
+}
+
+/// A sequence of cells the head always goes through one after the other, including empty cells
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Run {
+    /// Direction of the head when entering the run
+    pub direction: Direction,
+    pub cells: Vec<RunCell>,
+}
+
+impl Run {
+    /// Position of the first cell of the run
+    pub fn entry(&self) -> Position {
+        self.cells[0].position
+    }
+}
+
+/// Where an [`Edge`] leads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    /// Index of a [`Run`] in [`ControlFlowGraph::runs`]
+    Run(usize),
+
+    /// The program halts, see [`Break::Halted`](crate::Break::Halted)
+    Halt,
+
+    /// The program errors
+    Error,
+
+    /// Where the head goes can't be known statically
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    /// The head steps in its direction
+    Step,
+
+    /// The head is moved by a `jmp` or a `jif`
+    Jump,
+
+    /// The head goes no further
+    Stop,
+}
+
+/// A link from the last cell of a [`Run`] to what comes next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Edge {
+    /// Index of a [`Run`] in [`ControlFlowGraph::runs`]
+    pub from: usize,
+    pub to: Target,
+    pub kind: EdgeKind,
+}
+
+/// Control flow graph of a [`Frame`], the first run is the one the head starts in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlFlowGraph {
+    pub runs: Vec<Run>,
+    pub edges: Vec<Edge>,
+}
+
+/// Successor of a head position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Successor {
+    Head(HeadKey, Flow),
+    Stop(Stop),
+}
+
+impl Successor {
+    /// Order successors, so that the graph is the same from one build to another
+    fn order(&self) -> (u8, u32, u32, u8) {
+        match self {
+            Self::Head((position, direction), flow) => {
+                let (x, y) = position.as_numeric();
+                let flow = match flow {
+                    Flow::Step => 0,
+                    Flow::Jump => 1,
+                };
+                let direction = match direction {
+                    Direction::Up => 0,
+                    Direction::Right => 1,
+                    Direction::Down => 2,
+                    Direction::Left => 3,
+                };
+
+                (flow, y, x, direction)
+            }
+            Self::Stop(stop) => {
+                let stop = match stop {
+                    Stop::Halt => 0,
+                    Stop::Error => 1,
+                    Stop::Unknown => 2,
+                };
+
+                (2, 0, 0, stop)
+            }
+        }
+    }
+}
+
+impl ControlFlowGraph {
+    /// Build the control flow graph of `frame`
+    pub fn new(frame: &Frame) -> Self {
+        let analysis = analysis::analyze(frame);
+
+        let mut successors: HashMap<HeadKey, Vec<Successor>> = HashMap::new();
+        let mut predecessors: HashMap<HeadKey, Vec<HeadKey>> = HashMap::new();
+        let mut jumped_to = HashSet::new();
+
+        for &(from, to, flow) in &analysis.transitions {
+            successors
+                .entry(from)
+                .or_default()
+                .push(Successor::Head(to, flow));
+            predecessors.entry(to).or_default().push(from);
+
+            if flow == Flow::Jump {
+                jumped_to.insert(to);
+            }
+        }
+        for &(at, stop) in &analysis.stops {
+            successors
+                .entry(at)
+                .or_default()
+                .push(Successor::Stop(stop));
+        }
+        for successors in successors.values_mut() {
+            successors.sort_by_key(Successor::order);
+        }
+
+        let entry = (frame.head.position, frame.head.direction);
+        let starts_run = |key: &HeadKey| match predecessors.get(key).map(Vec::as_slice) {
+            _ if *key == entry || jumped_to.contains(key) => true,
+            Some([predecessor]) => successors[predecessor].len() != 1,
+            _ => true,
+        };
+
+        let mut graph = Self {
+            runs: Vec::new(),
+            edges: Vec::new(),
+        };
+
+        let mut ids = HashMap::from([(entry, 0)]);
+        let mut queue = VecDeque::from([entry]);
+
+        while let Some(start) = queue.pop_front() {
+            let id = ids[&start];
+            let mut run = Run {
+                direction: start.1,
+                cells: Vec::new(),
+            };
+
+            let mut current = start;
+            loop {
+                run.cells.push(RunCell {
+                    position: current.0,
+                    content: frame.grid.get(current.0),
+                });
+
+                match successors.get(&current).map(Vec::as_slice) {
+                    Some([Successor::Head(next, Flow::Step)]) if !starts_run(next) => {
+                        current = *next;
+                    }
+                    _ => break,
+                }
+            }
+
+            for successor in successors.get(&current).into_iter().flatten() {
+                let (to, kind) = match *successor {
+                    Successor::Head(next, flow) => {
+                        let new_id = ids.len();
+                        let next_id = *ids.entry(next).or_insert_with(|| {
+                            queue.push_back(next);
+                            new_id
+                        });
+
+                        let kind = match flow {
+                            Flow::Step => EdgeKind::Step,
+                            Flow::Jump => EdgeKind::Jump,
+                        };
+
+                        (Target::Run(next_id), kind)
+                    }
+                    Successor::Stop(Stop::Halt) => (Target::Halt, EdgeKind::Stop),
+                    Successor::Stop(Stop::Error) => (Target::Error, EdgeKind::Stop),
+                    Successor::Stop(Stop::Unknown) => (Target::Unknown, EdgeKind::Stop),
+                };
+
+                graph.edges.push(Edge { from: id, to, kind });
+            }
+
+            graph.runs.push(run);
+        }
+
+        graph
+    }
+
+    /// Write `self` as pretty JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .expect("a control flow graph should always be serializable")
+    }
+
+    /// Write `self` in the Graphviz DOT language
+    ///
+    /// Each run is labelled with its entry position and direction, followed by its non empty
+    /// cells. Jumps are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        // Writing to a `String` never fails
+        let _ = self.write_dot(&mut dot);
+
+        dot
+    }
+
+    fn write_dot(&self, dot: &mut String) -> std::fmt::Result {
+        writeln!(dot, "digraph grai {{")?;
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];")?;
+        writeln!(dot)?;
+        writeln!(dot, "    start [shape=point];")?;
+        if !self.runs.is_empty() {
+            writeln!(dot, "    start -> run0;")?;
+        }
+
+        for (id, run) in self.runs.iter().enumerate() {
+            let mut label = format!("{} {}\\l", run.entry(), run.direction);
+            for cell in run.cells.iter().filter(|cell| !cell.content.is_empty()) {
+                label.push_str(&format!(
+                    "{}  {}\\l",
+                    cell.position,
+                    escape(cell.content.as_str())
+                ));
+            }
+
+            writeln!(dot, "    run{id} [label=\"{label}\"];")?;
+        }
+
+        let mut terminals = Vec::new();
+        for edge in &self.edges {
+            let to = match edge.to {
+                Target::Run(id) => format!("run{id}"),
+                terminal => {
+                    if !terminals.contains(&terminal) {
+                        terminals.push(terminal);
+                    }
+
+                    terminal_name(terminal).to_string()
+                }
+            };
+
+            let attributes = match edge.kind {
+                EdgeKind::Step | EdgeKind::Stop => "",
+                EdgeKind::Jump => " [style=dashed]",
+            };
+
+            writeln!(dot, "    run{} -> {to}{attributes};", edge.from)?;
+        }
+
+        for terminal in terminals {
+            let shape = match terminal {
+                Target::Halt => "doublecircle",
+                Target::Error => "octagon",
+                Target::Unknown | Target::Run(_) => "diamond",
+            };
+
+            writeln!(
+                dot,
+                "    {0} [shape={shape}, label=\"{0}\"];",
+                terminal_name(terminal)
+            )?;
+        }
+
+        writeln!(dot, "}}")
+    }
+}
+
+fn terminal_name(target: Target) -> &'static str {
+    match target {
+        Target::Halt => "halt",
+        Target::Error => "error",
+        Target::Unknown | Target::Run(_) => "unknown",
+    }
+}
+
+/// Escape `string` to be used in a DOT quoted string
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+
+    for char in string.chars() {
+        match char {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            '\n' => escaped.push_str("\\n"),
+            char if char.is_control() => escaped.push(' '),
+            char => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn graph(json: serde_json::Value) -> ControlFlowGraph {
+        ControlFlowGraph::new(&serde_json::from_value(json).expect("must be a valid frame"))
+    }
+
+    fn positions(run: &Run) -> Vec<String> {
+        run.cells
+            .iter()
+            .map(|cell| cell.position.as_textual_string())
+            .collect()
+    }
+
+    #[test]
+    fn straight() {
+        let graph = graph(json!({
+            "head": { "position": "8A", "direction": "Right" },
+            "grid": { "8A": "1", "+A": "2", "/A": "add" },
+            "stack": []
+        }));
+
+        let runs: Vec<_> = graph.runs.iter().map(positions).collect();
+        assert_eq!(runs, [vec!["8A", "9A", "+A"], vec!["/A"]]);
+
+        // `add` is executed again and again at the edge of the grid, until it errors
+        assert_eq!(
+            graph.edges,
+            [
+                Edge {
+                    from: 0,
+                    to: Target::Run(1),
+                    kind: EdgeKind::Step
+                },
+                Edge {
+                    from: 1,
+                    to: Target::Run(1),
+                    kind: EdgeKind::Step
+                },
+                Edge {
+                    from: 1,
+                    to: Target::Error,
+                    kind: EdgeKind::Stop
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn turns() {
+        let graph = ControlFlowGraph::new(&Frame::from_example("loop").unwrap());
+
+        assert_eq!(graph.runs.len(), 2);
+        assert_eq!(positions(&graph.runs[0]), ["AA"]);
+        assert_eq!(positions(&graph.runs[1]), ["BA", "BB", "AB", "AA"]);
+        assert_eq!(graph.runs[1].direction, Direction::Right);
+
+        assert_eq!(
+            graph.edges,
+            [
+                Edge {
+                    from: 0,
+                    to: Target::Run(1),
+                    kind: EdgeKind::Step
+                },
+                Edge {
+                    from: 1,
+                    to: Target::Run(1),
+                    kind: EdgeKind::Step
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn branches() {
+        let graph = graph(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": {
+                "AA": "1", "BA": "2", "CA": "equ", "DA": "@AC", "EA": "jif", "FA": "gdo",
+                "AC": "@FD", "BC": "jmp",
+                "FD": "gle", "ED": "&AA", "DD": "jmp"
+            },
+            "stack": []
+        }));
+
+        // `FD` is entered going down from `FA`, and going right from the `jmp` at `BC`
+        let runs: Vec<_> = graph.runs.iter().map(positions).collect();
+        assert_eq!(
+            runs,
+            [
+                vec!["AA", "BA", "CA", "DA", "EA"],
+                vec!["FA", "FB", "FC", "FD"],
+                vec!["AC", "BC"],
+                vec!["ED", "DD"],
+                vec!["FD"],
+            ]
+        );
+
+        assert_eq!(
+            graph.edges,
+            [
+                Edge {
+                    from: 0,
+                    to: Target::Run(1),
+                    kind: EdgeKind::Step
+                },
+                Edge {
+                    from: 0,
+                    to: Target::Run(2),
+                    kind: EdgeKind::Jump
+                },
+                Edge {
+                    from: 1,
+                    to: Target::Run(3),
+                    kind: EdgeKind::Step
+                },
+                Edge {
+                    from: 2,
+                    to: Target::Run(4),
+                    kind: EdgeKind::Jump
+                },
+                Edge {
+                    from: 3,
+                    to: Target::Error,
+                    kind: EdgeKind::Stop
+                },
+                Edge {
+                    from: 4,
+                    to: Target::Run(3),
+                    kind: EdgeKind::Step
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn export() {
+        let graph = ControlFlowGraph::new(&Frame::from_example("countdown").unwrap());
+
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(
+            json["runs"][0]["cells"][0],
+            json!({ "position": "AA", "content": "@AB" })
+        );
+        assert_eq!(
+            json["edges"][0],
+            json!({ "from": 0, "to": { "run": 1 }, "kind": "step" })
+        );
+        assert_eq!(
+            serde_json::from_str::<ControlFlowGraph>(&graph.to_json()).unwrap(),
+            graph
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("run0 [label=\"AA right\\lAA  @AB\\lBA  1\\l"));
+        assert!(dot.contains("run0 -> run0 [style=dashed];"));
+        assert!(dot.contains("run0 -> run1;"));
+        assert!(dot.contains("run1 -> halt;"));
+        assert!(dot.contains("halt [shape=doublecircle, label=\"halt\"];"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(super::escape(r#"a"\"#), r#"a\"\\"#);
+    }
+}
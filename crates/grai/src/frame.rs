@@ -1,9 +1,14 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, RwLock},
+};
 
-use act::{Action, Revert, State};
+use act::{Action, AnyAction, Revert, State, TimelineRef};
 use serde::{Deserialize, Serialize};
 
 pub mod examples;
+pub mod snapshot;
 
 mod grid;
 pub use grid::*;
@@ -13,32 +18,308 @@ pub use stack::*;
 
 mod head;
 pub use head::*;
+
+mod debug;
+pub use debug::*;
+
+mod file;
+pub use file::*;
+
+mod observer;
+pub use observer::*;
+
+mod fuel;
+pub use fuel::*;
+
+mod host;
+pub use host::*;
+
+mod random;
+pub use random::*;
+
+mod protection;
+pub use protection::*;
+
+mod annotation;
+pub use annotation::*;
 use unwrap_infallible::UnwrapInfallible;
 
-use crate::{EvaluationError, FetchOperandError, Word};
+use crate::{CustomOpcodes, EvaluationError, FetchOperandError, Word, profile::Profile};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Frame {
     pub head: Head,
     pub grid: Grid,
     pub stack: Stack,
+
+    #[serde(default, skip_serializing_if = "DebugSettings::is_empty")]
+    pub debug: DebugSettings,
+
+    /// Regions the program can not write to, see [`Protections`]
+    #[serde(default, skip_serializing_if = "Protections::is_empty")]
+    pub protections: Protections,
+
+    /// Comments, names and tags for the readers of the program, see [`Annotations`]
+    #[serde(default, skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+
+    /// Limits the work of the frame when set, see [`Fuel`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<Fuel>,
+
+    /// Generator of the numbers drawn by `rnd`, see [`Random`]
+    #[serde(default, skip_serializing_if = "Random::is_default")]
+    pub random: Random,
+
+    /// Counts every step when set, see [`profile`](crate::profile)
+    #[serde(skip)]
+    pub profile: Option<Profile>,
+
+    /// Rust functions the program can call, see [`HostFunctions`]
+    #[serde(skip)]
+    pub functions: HostFunctions,
+
+    /// Opcodes registered by the embedding application, see [`CustomOpcodes`]
+    #[serde(skip)]
+    pub opcodes: CustomOpcodes,
+
+    #[serde(skip)]
+    pub(crate) observers: Observers,
+
+    /// Watchpoints hit by the last step
+    #[serde(skip)]
+    pub(crate) watch_hits: Vec<WatchHit>,
 }
 
 impl Frame {
+    /// Execute the word under the head
+    ///
+    /// # Errors
+    /// Returns an error if the word could not be executed, or if the frame does not have enough
+    /// [`Fuel`] left for its word, in either case the frame is left unchanged
     pub fn step(&mut self) -> Result<Revert, <Frame as State>::Error> {
-        let cell = self.grid.get(self.head.position);
+        self.watch_hits.clear();
 
-        if cell.is_empty() {
-            self.act(HeadAction::Step)
-        } else {
-            match Word::from_cell(cell) {
-                Word::Opcode(opcode) => Ok(opcode.evaluate(self)?),
-                Word::Operand(operand) => {
-                    let push = self.act(StackAction::Push(operand))?;
-                    let step = self.act(HeadAction::Step)?;
+        let watched = self.debug.has_watchpoints();
+        if !watched && self.fuel.is_none() && self.profile.is_none() {
+            return self.step_unwatched();
+        }
 
-                    Ok(vec![push, step].into())
+        let position = self.head.position;
+        let opcode = match self.grid.word(position) {
+            Some(Word::Opcode(opcode)) => Some(*opcode),
+            _ => None,
+        };
+
+        // Fail early when even the word itself is too costly, reads are only known after the step
+        let cost = match &self.fuel {
+            Some(fuel) => {
+                let cost = fuel.costs.word(self.word(position).as_ref());
+                if cost > fuel.remaining() {
+                    return Err(FrameError::OutOfFuel {
+                        needed: cost,
+                        remaining: fuel.remaining(),
+                    });
                 }
+
+                cost
+            }
+            None => 0,
+        };
+
+        let depth = self.stack.iter().len();
+        self.grid.record_reads();
+        let result = self.step_unwatched();
+        let reads = self.grid.take_reads();
+        let revert = result?;
+
+        // The step is kept even if its reads overdraw the fuel, the host functions it called can't
+        // be undone, the next step runs out of fuel instead
+        if let Some(fuel) = &mut self.fuel {
+            fuel.consume(cost + reads.positions.len() as u64 * fuel.costs.read);
+        }
+
+        if watched {
+            let written: Vec<Position> = revert
+                .actions()
+                .filter_map(|action| action.downcast_ref::<GridAction>())
+                .flat_map(|action| action.restored())
+                .map(|(position, _)| position)
+                .collect();
+
+            self.watch_hits =
+                self.debug
+                    .watch(&written, &reads.positions, (depth, self.stack.iter().len()));
+        }
+
+        if let Some(profile) = &mut self.profile {
+            let depth = depth.max(self.stack.iter().len());
+            profile.record(position, opcode, depth, &reads.chains);
+        }
+
+        Ok(revert)
+    }
+
+    /// The [watchpoints](Watchpoint) hit by the last step, empty if the last step errored
+    pub fn watch_hits(&self) -> &[WatchHit] {
+        &self.watch_hits
+    }
+
+    /// The [`Word`] the frame executes at `position`, `None` if the cell is empty
+    ///
+    /// Unlike [`Grid::word()`], a literal holding the name of one of the frame's
+    /// [custom opcodes](CustomOpcodes) is that opcode.
+    pub fn word(&self, position: Position) -> Option<Word> {
+        let word = self.grid.word(position)?;
+
+        Some(
+            self.opcodes
+                .named_by(word)
+                .map_or_else(|| word.clone(), Word::Custom),
+        )
+    }
+
+    fn step_unwatched(&mut self) -> Result<Revert, <Frame as State>::Error> {
+        match self.word(self.head.position) {
+            None => self.act(HeadAction::Step),
+            Some(Word::Opcode(opcode)) => Ok(opcode.evaluate(self)?),
+            Some(Word::Custom(opcode)) => opcode.evaluate(self),
+            Some(Word::Operand(operand)) => {
+                let push = self.act(StackAction::Push(operand))?;
+                let step = self.act(HeadAction::Step)?;
+
+                Ok(vec![push, step].into())
+            }
+        }
+    }
+
+    /// Notify `observer` of every change applied to the frame from now on, see [`FrameEvent`]
+    ///
+    /// Only the changes applied through the frame are observed, writes made directly to
+    /// [`Frame::grid`] (with [`Grid::set()`], [`Grid::get_mut()`] or [`Grid::act()`](act::State::act))
+    /// are not.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use grai::{Frame, FrameEvent};
+    /// let mut frame = Frame::from_example("countdown").unwrap();
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let id = frame.observe({
+    ///     let events = Arc::clone(&events);
+    ///     move |event: &FrameEvent| events.lock().unwrap().push(event.clone())
+    /// });
+    ///
+    /// let _ = frame.step().unwrap();
+    /// assert!(matches!(events.lock().unwrap()[0], FrameEvent::Pushed { depth: 1, .. }));
+    ///
+    /// assert!(frame.unobserve(id));
+    /// ```
+    pub fn observe(&mut self, observer: impl Observer + 'static) -> ObserverId {
+        self.observers.add(observer)
+    }
+
+    /// Stop notifying an observer, returns `false` if it was not observing this frame
+    pub fn unobserve(&mut self, id: ObserverId) -> bool {
+        self.observers.remove(id)
+    }
+
+    fn act_grid(&mut self, action: GridAction) -> Revert {
+        if self.observers.is_empty() {
+            return self.grid.act(action).unwrap_infallible();
+        }
+
+        let revert = self.grid.act(action).unwrap_infallible();
+
+        // The revert holds the cells as they were before the action
+        let restored = revert
+            .actions()
+            .filter_map(|action| action.downcast_ref::<GridAction>())
+            .flat_map(|action| action.restored());
+        for (position, before) in restored {
+            let after = self.grid.get(position);
+
+            if *before != after {
+                self.observers.notify(&FrameEvent::Cell {
+                    position,
+                    before: before.clone(),
+                    after,
+                });
+            }
+        }
+
+        revert
+    }
+
+    fn act_head(&mut self, action: HeadAction) -> Revert {
+        let before = self.head;
+        let revert = self.head.act(action).unwrap_infallible();
+
+        if !self.observers.is_empty() {
+            self.observers.notify(&FrameEvent::Head {
+                before,
+                after: self.head,
+            });
+        }
+
+        revert
+    }
+
+    fn act_stack(&mut self, action: StackAction) -> Result<Revert, StackError> {
+        if self.observers.is_empty() {
+            return self.stack.act(action);
+        }
+
+        let popped = self.stack.last().cloned();
+        let revert = self.stack.act(action.clone())?;
+        let depth = self.stack.iter().len();
+
+        let event = match action {
+            StackAction::Push(operand) => FrameEvent::Pushed { operand, depth },
+            StackAction::Pop => FrameEvent::Popped {
+                operand: popped.expect("a successful pop should have popped an operand"),
+                depth,
+            },
+        };
+        self.observers.notify(&event);
+
+        Ok(revert)
+    }
+
+    /// Apply a [`Revert`] previously returned by [`Frame::act()`], bringing the frame back to its
+    /// prior state
+    ///
+    /// # Errors
+    /// Returns an error if one of the reverting actions could not be applied, meaning the frame was
+    /// changed in between, without that change being reverted first
+    pub fn revert(&mut self, revert: Revert) -> Result<(), FrameError> {
+        for action in revert.into_actions() {
+            let action = FrameAction::from_any_action(action)
+                .expect("a frame revert should only contain frame actions");
+
+            // The revert of a revert is not needed
+            let _ = self.act(action)?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the changes of `apply` through a [`TimelineRef`], if it fails the changes it already
+    /// applied are reverted, so that a failed step leaves the frame unchanged
+    pub(crate) fn apply_or_rollback(
+        &mut self,
+        apply: impl FnOnce(&mut TimelineRef<Frame>) -> Result<(), FrameError>,
+    ) -> Result<Revert, FrameError> {
+        let mut frame = TimelineRef::new(self);
+        let result = apply(&mut frame);
+        let revert = frame.into_revert();
+
+        match result {
+            Ok(()) => Ok(revert),
+            Err(err) => {
+                self.revert(revert)?;
+                Err(err)
             }
         }
     }
@@ -54,18 +335,50 @@ pub enum FrameError {
 
     #[error("while in evaluation, fetch operand error : {0}")]
     FetchOperand(#[from] FetchOperandError),
+
+    #[error("out of fuel, the step needs {needed} but only {remaining} remains")]
+    OutOfFuel { needed: u64, remaining: u64 },
 }
 
 #[derive(Debug, Clone)]
 pub enum FrameAction {
     Step,
     // Run,
+    SetBreakpoint(Position),
+    RemoveBreakpoint(Position),
+    ToggleBreakpoint(Position),
+    AddWatchpoint(Watchpoint),
+    RemoveWatchpoint(Watchpoint),
+    Protect(Region),
+    Unprotect(Region),
 
-    // SetBreakpoint(Position),
-    // ToggleBreakpoint(Position),
     Grid(GridAction),
     Stack(StackAction),
     Head(HeadAction),
+    Random(RandomAction),
+    Annotation(AnnotationAction),
+}
+
+impl FrameAction {
+    /// Get `Self` back from a type-erased [`AnyAction`], either a `FrameAction` or one of the
+    /// action of the frame's components
+    pub fn from_any_action(action: AnyAction) -> Option<Self> {
+        if let Some(action) = action.downcast_ref::<FrameAction>() {
+            Some(action.clone())
+        } else if let Some(action) = action.downcast_ref::<GridAction>() {
+            Some(action.clone().into())
+        } else if let Some(action) = action.downcast_ref::<StackAction>() {
+            Some(action.clone().into())
+        } else if let Some(action) = action.downcast_ref::<HeadAction>() {
+            Some(action.clone().into())
+        } else if let Some(action) = action.downcast_ref::<RandomAction>() {
+            Some(action.clone().into())
+        } else {
+            action
+                .downcast_ref::<AnnotationAction>()
+                .map(|action| action.clone().into())
+        }
+    }
 }
 
 impl From<GridAction> for FrameAction {
@@ -86,6 +399,18 @@ impl From<HeadAction> for FrameAction {
     }
 }
 
+impl From<RandomAction> for FrameAction {
+    fn from(value: RandomAction) -> Self {
+        Self::Random(value)
+    }
+}
+
+impl From<AnnotationAction> for FrameAction {
+    fn from(value: AnnotationAction) -> Self {
+        Self::Annotation(value)
+    }
+}
+
 impl Action for FrameAction {}
 
 impl State for Frame {
@@ -94,13 +419,69 @@ impl State for Frame {
 
     fn act(&mut self, action: impl Into<Self::Action>) -> Result<Revert, Self::Error> {
         match action.into() {
-            FrameAction::Grid(grid_action) => Ok(self.grid.act(grid_action).unwrap_infallible()),
-            FrameAction::Head(head_action) => Ok(self.head.act(head_action).unwrap_infallible()),
+            FrameAction::Grid(grid_action) => Ok(self.act_grid(grid_action)),
+            FrameAction::Head(head_action) => Ok(self.act_head(head_action)),
+            FrameAction::Random(random_action) => {
+                Ok(self.random.act(random_action).unwrap_infallible())
+            }
+            FrameAction::Annotation(annotation_action) => {
+                Ok(self.annotations.act(annotation_action).unwrap_infallible())
+            }
             FrameAction::Stack(stack_action) => {
-                self.stack.act(stack_action).map_err(|err| err.into())
+                self.act_stack(stack_action).map_err(|err| err.into())
             }
 
             FrameAction::Step => self.step(),
+
+            FrameAction::SetBreakpoint(position) => {
+                if self.debug.set_breakpoint(position) {
+                    Ok(Revert::new(FrameAction::RemoveBreakpoint(position)))
+                } else {
+                    Ok(Revert::None)
+                }
+            }
+            FrameAction::RemoveBreakpoint(position) => {
+                if self.debug.remove_breakpoint(position) {
+                    Ok(Revert::new(FrameAction::SetBreakpoint(position)))
+                } else {
+                    Ok(Revert::None)
+                }
+            }
+            FrameAction::ToggleBreakpoint(position) => {
+                if !self.debug.remove_breakpoint(position) {
+                    self.debug.set_breakpoint(position);
+                }
+
+                Ok(Revert::new(FrameAction::ToggleBreakpoint(position)))
+            }
+            FrameAction::AddWatchpoint(watchpoint) => {
+                if self.debug.add_watchpoint(watchpoint) {
+                    Ok(Revert::new(FrameAction::RemoveWatchpoint(watchpoint)))
+                } else {
+                    Ok(Revert::None)
+                }
+            }
+            FrameAction::RemoveWatchpoint(watchpoint) => {
+                if self.debug.remove_watchpoint(watchpoint) {
+                    Ok(Revert::new(FrameAction::AddWatchpoint(watchpoint)))
+                } else {
+                    Ok(Revert::None)
+                }
+            }
+            FrameAction::Protect(region) => {
+                if self.protections.protect(region) {
+                    Ok(Revert::new(FrameAction::Unprotect(region)))
+                } else {
+                    Ok(Revert::None)
+                }
+            }
+            FrameAction::Unprotect(region) => {
+                if self.protections.unprotect(region) {
+                    Ok(Revert::new(FrameAction::Protect(region)))
+                } else {
+                    Ok(Revert::None)
+                }
+            }
         }
     }
 }
@@ -130,3 +511,43 @@ impl State for FrameGuard {
         self.write(|frame| frame.act(action))
     }
 }
+
+/// A handle to a [`Frame`] that can be shared between threads, unlike [`FrameGuard`]
+///
+/// The frame is behind a read-write lock, a [`Worker`](crate::worker::Worker) can run it on a
+/// background thread while others read it.
+#[derive(Debug, Clone)]
+pub struct SharedFrame(Arc<RwLock<Frame>>);
+
+impl SharedFrame {
+    pub fn new(frame: Frame) -> Self {
+        Self(Arc::new(RwLock::new(frame)))
+    }
+
+    pub fn read<T>(&self, reader: impl FnOnce(&Frame) -> T) -> T {
+        reader(
+            &self
+                .0
+                .read()
+                .expect("the frame lock should not be poisoned"),
+        )
+    }
+
+    pub fn write<T>(&mut self, writer: impl FnOnce(&mut Frame) -> T) -> T {
+        writer(
+            &mut self
+                .0
+                .write()
+                .expect("the frame lock should not be poisoned"),
+        )
+    }
+}
+
+impl State for SharedFrame {
+    type Action = FrameAction;
+    type Error = FrameError;
+
+    fn act(&mut self, action: impl Into<Self::Action>) -> Result<Revert, Self::Error> {
+        self.write(|frame| frame.act(action))
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    fmt::Display,
+    str::FromStr,
+};
+
+use act::{Action, Revert, State};
+use serde::{Deserialize, Serialize};
+
+use crate::{Position, Region};
+
+/// Notes left on a [`Frame`](crate::Frame) for the people reading the program, they are saved
+/// with the frame but never evaluated
+///
+/// Annotations are per-cell comments, names given to regions, and per-cell colour tags. They are
+/// changed through [`AnnotationAction`]s, so editors can undo them.
+///
+/// # Examples
+/// ```
+/// # use act::State;
+/// # use grai::{AnnotationAction, Frame, TagColor};
+/// let mut frame = Frame::from_example("countdown").unwrap();
+/// let start = "AA".parse().unwrap();
+///
+/// let _ = frame
+///     .act(AnnotationAction::Comment(start, Some("counts down from here".to_string())))
+///     .unwrap();
+/// let _ = frame
+///     .act(AnnotationAction::Label("loop".to_string(), Some("AA:DA".parse().unwrap())))
+///     .unwrap();
+/// let _ = frame
+///     .act(AnnotationAction::Tag(start, Some("#ff8800".parse().unwrap())))
+///     .unwrap();
+///
+/// let annotations = &frame.annotations;
+/// assert_eq!(annotations.comment(start), Some("counts down from here"));
+/// assert_eq!(annotations.labels_at(start).collect::<Vec<_>>(), ["loop"]);
+/// assert_eq!(annotations.tag(start), Some(TagColor::new(0xff, 0x88, 0x00)));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotations {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    comments: HashMap<Position, String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, Region>,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    tags: HashMap<Position, TagColor>,
+}
+
+/// The colour a cell is tagged with, written `#rrggbb`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub struct TagColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl TagColor {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("invalid tag colour `{0}`, expected `#` and six hexadecimal digits such as `#ff8800`")]
+pub struct TagColorError(String);
+
+impl FromStr for TagColor {
+    type Err = TagColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || TagColorError(s.to_string());
+
+        let digits = s.strip_prefix('#').ok_or_else(error)?;
+        if digits.len() != 6 || !digits.chars().all(|char| char.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+
+        let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16);
+        match (channel(0), channel(2), channel(4)) {
+            (Ok(r), Ok(g), Ok(b)) => Ok(Self::new(r, g, b)),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl TryFrom<String> for TagColor {
+    type Error = TagColorError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<TagColor> for String {
+    fn from(value: TagColor) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for TagColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl Annotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if there are no annotations
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty() && self.labels.is_empty() && self.tags.is_empty()
+    }
+
+    /// The comment left on the cell at `position`
+    pub fn comment(&self, position: Position) -> Option<&str> {
+        self.comments.get(&position).map(String::as_str)
+    }
+
+    /// Iterate over every comment, in no particular order
+    pub fn comments(&self) -> impl Iterator<Item = (Position, &str)> {
+        self.comments
+            .iter()
+            .map(|(position, comment)| (*position, comment.as_str()))
+    }
+
+    /// The region named `name`
+    pub fn label(&self, name: &str) -> Option<Region> {
+        self.labels.get(name).copied()
+    }
+
+    /// Iterate over every named region, by name
+    pub fn labels(&self) -> impl Iterator<Item = (&str, Region)> {
+        self.labels
+            .iter()
+            .map(|(name, region)| (name.as_str(), *region))
+    }
+
+    /// Iterate over the names of the regions holding `position`, by name
+    pub fn labels_at(&self, position: Position) -> impl Iterator<Item = &str> {
+        self.labels()
+            .filter(move |(_, region)| region.contains(position))
+            .map(|(name, _)| name)
+    }
+
+    /// The colour the cell at `position` is tagged with
+    pub fn tag(&self, position: Position) -> Option<TagColor> {
+        self.tags.get(&position).copied()
+    }
+
+    /// Iterate over every colour tag, in no particular order
+    pub fn tags(&self) -> impl Iterator<Item = (Position, TagColor)> {
+        self.tags
+            .iter()
+            .map(|(position, color)| (*position, *color))
+    }
+}
+
+/// A change to the [`Annotations`], `None` removing the annotation
+#[derive(Debug, Clone)]
+pub enum AnnotationAction {
+    Comment(Position, Option<String>),
+    Label(String, Option<Region>),
+    Tag(Position, Option<TagColor>),
+}
+
+impl Action for AnnotationAction {}
+
+impl State for Annotations {
+    type Action = AnnotationAction;
+    type Error = Infallible;
+
+    fn act(&mut self, action: impl Into<Self::Action>) -> Result<Revert, Self::Error> {
+        let revert = match action.into() {
+            AnnotationAction::Comment(position, comment) => {
+                let last = match comment {
+                    Some(comment) => self.comments.insert(position, comment),
+                    None => self.comments.remove(&position),
+                };
+                AnnotationAction::Comment(position, last)
+            }
+            AnnotationAction::Label(name, region) => {
+                let last = match region {
+                    Some(region) => self.labels.insert(name.clone(), region),
+                    None => self.labels.remove(&name),
+                };
+                AnnotationAction::Label(name, last)
+            }
+            AnnotationAction::Tag(position, color) => {
+                let last = match color {
+                    Some(color) => self.tags.insert(position, color),
+                    None => self.tags.remove(&position),
+                };
+                AnnotationAction::Tag(position, last)
+            }
+        };
+
+        Ok(Revert::new(revert))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Frame, FrameAction, position};
+
+    #[test]
+    fn undo() {
+        let mut frame = Frame::from_text("A |1  |2  |add|").unwrap();
+        let at = position("CA");
+
+        let first = frame
+            .act(AnnotationAction::Comment(at, Some("sum".to_string())))
+            .unwrap();
+        let second = frame
+            .act(AnnotationAction::Comment(at, Some("total".to_string())))
+            .unwrap();
+        let removed = frame.act(AnnotationAction::Comment(at, None)).unwrap();
+        assert_eq!(frame.annotations.comment(at), None);
+
+        frame.revert(removed).unwrap();
+        assert_eq!(frame.annotations.comment(at), Some("total"));
+        frame.revert(second).unwrap();
+        assert_eq!(frame.annotations.comment(at), Some("sum"));
+        frame.revert(first).unwrap();
+        assert!(frame.annotations.is_empty());
+
+        // Annotations are not evaluated
+        let _ = frame
+            .act(FrameAction::Annotation(AnnotationAction::Tag(
+                position("DA"),
+                Some(TagColor::new(0, 0, 0)),
+            )))
+            .unwrap();
+        for _ in 0..3 {
+            let _ = frame.step().unwrap();
+        }
+        assert_eq!(frame.stack.iter().len(), 1);
+        assert_eq!(frame.grid.get(position("DA")).as_str(), "");
+    }
+
+    #[test]
+    fn serialize() {
+        let mut annotations = Annotations::new();
+        let region: Region = "AB:CC".parse().unwrap();
+        let _ = annotations
+            .act(AnnotationAction::Label("data".to_string(), Some(region)))
+            .unwrap();
+        let _ = annotations
+            .act(AnnotationAction::Label(
+                "all".to_string(),
+                Some("AA:CC".parse().unwrap()),
+            ))
+            .unwrap();
+        let _ = annotations
+            .act(AnnotationAction::Tag(
+                position("BB"),
+                Some(TagColor::new(0x12, 0x34, 0x56)),
+            ))
+            .unwrap();
+
+        assert_eq!(annotations.label("data"), Some(region));
+        assert_eq!(
+            annotations.labels_at(position("BB")).collect::<Vec<_>>(),
+            ["all", "data"]
+        );
+        assert_eq!(annotations.labels_at(position("DD")).count(), 0);
+
+        let json = serde_json::to_value(&annotations).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "labels": { "all": "AA:CC", "data": "AB:CC" },
+                "tags": { "BB": "#123456" }
+            })
+        );
+
+        let read: Annotations = serde_json::from_value(json).unwrap();
+        assert_eq!(read, annotations);
+    }
+
+    #[test]
+    fn tag_color() {
+        let color: TagColor = "#0aF0c8".parse().unwrap();
+        assert_eq!(color, TagColor::new(0x0a, 0xf0, 0xc8));
+        assert_eq!(color.to_string(), "#0af0c8");
+
+        for invalid in ["0af0c8", "#0af0c", "#0af0c8f", "#0ag0c8", "#+1+2+3"] {
+            assert_eq!(
+                invalid.parse::<TagColor>(),
+                Err(TagColorError(invalid.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Position, Region};
+
+/// Debugging settings of a [`Frame`](crate::Frame), they never change how a program is evaluated,
+/// but tell tools driving the execution where to stop
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DebugSettings {
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    breakpoints: HashSet<Position>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    watchpoints: Vec<Watchpoint>,
+}
+
+/// A data watchpoint, stopping the execution when some data is accessed, see
+/// [`Frame::watch_hits()`](crate::Frame::watch_hits)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Watchpoint {
+    /// A cell of the region is written
+    Write(Region),
+
+    /// A cell of the region is read, through the resolution of an address or a pointer
+    Read(Region),
+
+    /// The depth of the stack crosses the threshold, going from below it to at least it, or back
+    StackDepth(usize),
+}
+
+/// A [`Watchpoint`] hit during a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watchpoint: Watchpoint,
+    pub event: WatchEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEvent {
+    Written(Position),
+    Read(Position),
+    StackDepth { before: usize, after: usize },
+}
+
+impl DebugSettings {
+    /// Obtain new empty `DebugSettings`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no settings are set
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty() && self.watchpoints.is_empty()
+    }
+
+    /// Returns `true` if a breakpoint is set at the given [`Position`]
+    pub fn has_breakpoint(&self, position: Position) -> bool {
+        self.breakpoints.contains(&position)
+    }
+
+    /// Set a breakpoint at the given [`Position`], returns `false` if it was already set
+    pub fn set_breakpoint(&mut self, position: Position) -> bool {
+        self.breakpoints.insert(position)
+    }
+
+    /// Remove the breakpoint at the given [`Position`], returns `false` if there was none
+    pub fn remove_breakpoint(&mut self, position: Position) -> bool {
+        self.breakpoints.remove(&position)
+    }
+
+    /// Iterate over every breakpoints, in no particular order
+    pub fn breakpoints(&self) -> impl Iterator<Item = &Position> {
+        self.breakpoints.iter()
+    }
+
+    /// Add a watchpoint, returns `false` if it was already set
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+        if self.watchpoints.contains(&watchpoint) {
+            false
+        } else {
+            self.watchpoints.push(watchpoint);
+            true
+        }
+    }
+
+    /// Remove a watchpoint, returns `false` if it was not set
+    pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|set| *set != watchpoint);
+
+        self.watchpoints.len() != len
+    }
+
+    pub fn has_watchpoints(&self) -> bool {
+        !self.watchpoints.is_empty()
+    }
+
+    /// Iterate over every watchpoints, in the order they were added
+    pub fn watchpoints(&self) -> impl Iterator<Item = &Watchpoint> {
+        self.watchpoints.iter()
+    }
+
+    /// Every watchpoint hit by a step, given the cells it wrote and read, and the depth of the
+    /// stack before and after it
+    pub(crate) fn watch(
+        &self,
+        written: &[Position],
+        read: &[Position],
+        depth: (usize, usize),
+    ) -> Vec<WatchHit> {
+        let mut hits = Vec::new();
+        let mut hit = |watchpoint: Watchpoint, event| {
+            let hit = WatchHit { watchpoint, event };
+            if !hits.contains(&hit) {
+                hits.push(hit);
+            }
+        };
+
+        for &watchpoint in &self.watchpoints {
+            match watchpoint {
+                Watchpoint::Write(region) => written
+                    .iter()
+                    .filter(|position| region.contains(**position))
+                    .for_each(|position| hit(watchpoint, WatchEvent::Written(*position))),
+                Watchpoint::Read(region) => read
+                    .iter()
+                    .filter(|position| region.contains(**position))
+                    .for_each(|position| hit(watchpoint, WatchEvent::Read(*position))),
+                Watchpoint::StackDepth(threshold) => {
+                    let (before, after) = depth;
+                    if (before < threshold) != (after < threshold) {
+                        hit(watchpoint, WatchEvent::StackDepth { before, after });
+                    }
+                }
+            }
+        }
+
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Frame, position};
+
+    #[test]
+    fn watch_hits() {
+        let mut frame = Frame::from_text(
+            "A |5  |@AB|set|@AB|1  |add|&CC|equ|\n\
+             C |   |   |@AB|",
+        )
+        .unwrap();
+
+        let write = Watchpoint::Write("AB:BB".parse().unwrap());
+        let read = Watchpoint::Read(Region::cell(position("AB")));
+        let depth = Watchpoint::StackDepth(2);
+        for watchpoint in [write, read, depth] {
+            frame.debug.add_watchpoint(watchpoint);
+        }
+
+        let mut hits = Vec::new();
+        for _ in 0..8 {
+            let _ = frame.step().unwrap();
+            hits.push(frame.watch_hits().to_vec());
+        }
+
+        let hit = |watchpoint, event| vec![WatchHit { watchpoint, event }];
+        assert_eq!(
+            hits,
+            [
+                vec![],
+                hit(
+                    depth,
+                    WatchEvent::StackDepth {
+                        before: 1,
+                        after: 2
+                    }
+                ),
+                [
+                    hit(write, WatchEvent::Written(position("AB"))),
+                    hit(
+                        depth,
+                        WatchEvent::StackDepth {
+                            before: 2,
+                            after: 0
+                        }
+                    ),
+                ]
+                .concat(),
+                vec![],
+                hit(
+                    depth,
+                    WatchEvent::StackDepth {
+                        before: 1,
+                        after: 2
+                    }
+                ),
+                [
+                    hit(read, WatchEvent::Read(position("AB"))),
+                    hit(
+                        depth,
+                        WatchEvent::StackDepth {
+                            before: 2,
+                            after: 1
+                        }
+                    ),
+                ]
+                .concat(),
+                hit(
+                    depth,
+                    WatchEvent::StackDepth {
+                        before: 1,
+                        after: 2
+                    }
+                ),
+                // Reads through a pointer
+                [
+                    hit(read, WatchEvent::Read(position("AB"))),
+                    hit(
+                        depth,
+                        WatchEvent::StackDepth {
+                            before: 2,
+                            after: 1
+                        }
+                    ),
+                ]
+                .concat(),
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let mut settings = DebugSettings::new();
+        settings.add_watchpoint(Watchpoint::Write("AA:BB".parse().unwrap()));
+        settings.add_watchpoint(Watchpoint::Read(Region::cell(position("CC"))));
+        settings.add_watchpoint(Watchpoint::StackDepth(10));
+        assert!(!settings.add_watchpoint(Watchpoint::StackDepth(10)));
+
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(
+            json,
+            json!({ "watchpoints": [{ "write": "AA:BB" }, { "read": "CC" }, { "stack_depth": 10 }] })
+        );
+
+        let settings: DebugSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(settings.watchpoints().count(), 3);
+        assert!(!settings.is_empty());
+    }
+}
@@ -1,6 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::Frame;
+use crate::{Frame, FrameFile};
 
 #[iftree::include_file_tree("paths = '/assets/**'")]
 pub struct Example {
@@ -8,11 +8,31 @@ pub struct Example {
     contents_str: &'static str,
 }
 
+/// Iterate over the names of every bundled examples, usable with [`Frame::from_example()`]
+pub fn names() -> impl Iterator<Item = &'static str> {
+    ASSETS.iter().filter_map(|file| {
+        Path::new(file.relative_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+    })
+}
+
 impl Frame {
     pub fn from_example(file_name: &str) -> Option<Self> {
         ASSETS.iter().find_map(|file| {
             if PathBuf::from(file_name) == PathBuf::from(file.relative_path).file_stem()? {
-                let frame = serde_json::from_str(file.contents_str).unwrap_or_else(|err| {
+                let frame = match Path::new(file.relative_path)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                {
+                    Some("gra") => {
+                        Frame::from_text(file.contents_str).map_err(|err| err.to_string())
+                    }
+                    _ => FrameFile::from_json(file.contents_str)
+                        .map(|file| file.frame)
+                        .map_err(|err| err.to_string()),
+                }
+                .unwrap_or_else(|err| {
                     panic!(
                         "example must contain valid frame, trying to parse `{}`, got error {}",
                         file.relative_path, err
@@ -30,7 +50,10 @@ impl Frame {
 mod tests {
     use std::path::PathBuf;
 
+    use act::State;
+
     use super::*;
+    use crate::FrameAction;
 
     #[test]
     fn fetch_each() {
@@ -47,4 +70,30 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn countdown() {
+        let mut frame = Frame::from_example("countdown").unwrap();
+        let counter = "AB".parse().unwrap();
+
+        for _ in 0..200 {
+            let _ = frame.act(FrameAction::Step).unwrap();
+        }
+
+        assert_eq!(frame.grid.get(counter).as_str(), "0");
+        assert!(frame.stack.last().is_none());
+    }
+
+    #[test]
+    fn looping() {
+        let mut frame = Frame::from_example("loop").unwrap();
+
+        let mut positions = Vec::new();
+        for _ in 0..4 {
+            let _ = frame.act(FrameAction::Step).unwrap();
+            positions.push(frame.head.position.as_textual_string());
+        }
+
+        assert_eq!(positions, ["BA", "BB", "AB", "AA"]);
+    }
 }
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Frame;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrameFileError {
+    #[error("invalid json : {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid version, expected a positive integer, found `{got}`")]
+    InvalidVersion { got: Value },
+
+    #[error(
+        "unsupported version `{got}`, latest supported is `{}`",
+        FrameFile::VERSION
+    )]
+    UnsupportedVersion { got: u64 },
+
+    #[error("could not migrate from version `{from}` : {reason}")]
+    Migration { from: u64, reason: String },
+
+    #[error("invalid frame file at `{}` : {}", .0.path(), .0.inner())]
+    Deserialize(#[from] serde_path_to_error::Error<serde_json::Error>),
+}
+
+/// Informations about a program, not used by the interpreter
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A `FrameFile` is the versioned envelope in which a [`Frame`] is stored, along with its
+/// [`Metadata`]
+///
+/// Files written with an older version are upgraded when loaded, see [`FrameFile::from_json()`].
+/// A bare frame, without any envelope, is considered to be version `0`.
+///
+/// # Examples
+/// ```
+/// # use grai::FrameFile;
+/// let json = r#"{
+///     "version": 1,
+///     "metadata": { "title": "Nothing" },
+///     "frame": { "head": { "position": "AA", "direction": "Right" }, "grid": {}, "stack": [] }
+/// }"#;
+///
+/// let file = FrameFile::from_json(json).unwrap();
+/// assert_eq!(file.metadata.title.as_deref(), Some("Nothing"));
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrameFile {
+    pub version: u64,
+
+    #[serde(default)]
+    pub metadata: Metadata,
+
+    pub frame: Frame,
+}
+
+/// Upgrade a file from one version to the next
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// Migrations, the one at index `n` upgrades a file from version `n` to version `n + 1`
+const MIGRATIONS: [Migration; FrameFile::VERSION as usize] = [wrap_bare_frame];
+
+/// Version `0` is a bare frame, wrap it in an envelope, an envelope explicitly written at version
+/// `0` is already in the shape of version `1`
+fn wrap_bare_frame(frame: Value) -> Result<Value, String> {
+    match frame {
+        Value::Object(mut envelope) if envelope.contains_key("version") => {
+            envelope.insert(String::from("version"), Value::from(1));
+            Ok(Value::Object(envelope))
+        }
+        Value::Object(_) => Ok(serde_json::json!({
+            "version": 1,
+            "frame": frame,
+        })),
+        _ => Err(String::from("expected a frame object")),
+    }
+}
+
+impl FrameFile {
+    /// The current version of the format, the one files are written with
+    pub const VERSION: u64 = 1;
+
+    /// Obtain a new `FrameFile` at the current version
+    pub fn new(frame: Frame, metadata: Metadata) -> Self {
+        Self {
+            version: Self::VERSION,
+            metadata,
+            frame,
+        }
+    }
+
+    /// Parse a `FrameFile` from JSON, upgrading it to the current version if needed
+    ///
+    /// # Errors
+    /// Returns an error if the JSON is invalid, if its version is unsupported, or if the upgraded
+    /// file does not describe a valid frame, in which case the error holds the path to the
+    /// faulty value
+    pub fn from_json(json: &str) -> Result<Self, FrameFileError> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// Same as [`FrameFile::from_json()`], from an already parsed JSON [`Value`]
+    pub fn from_value(mut value: Value) -> Result<Self, FrameFileError> {
+        let mut version = Self::version_of(&value)?;
+
+        if version > Self::VERSION {
+            return Err(FrameFileError::UnsupportedVersion { got: version });
+        }
+
+        while version < Self::VERSION {
+            let migration = MIGRATIONS[version as usize];
+            value = migration(value).map_err(|reason| FrameFileError::Migration {
+                from: version,
+                reason,
+            })?;
+
+            version = Self::version_of(&value)?;
+        }
+
+        Ok(serde_path_to_error::deserialize(value)?)
+    }
+
+    /// Write `self` as pretty JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a frame file should always be serializable")
+    }
+
+    fn version_of(value: &Value) -> Result<u64, FrameFileError> {
+        match value.get("version") {
+            None => Ok(0),
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| FrameFileError::InvalidVersion {
+                    got: version.clone(),
+                }),
+        }
+    }
+}
+
+impl From<Frame> for FrameFile {
+    fn from(frame: Frame) -> Self {
+        Self::new(frame, Metadata::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn bare_frame() -> Result<(), FrameFileError> {
+        let file = FrameFile::from_value(json!({
+            "head": { "position": "BA", "direction": "Down" },
+            "grid": { "AA": "abc" },
+            "stack": []
+        }))?;
+
+        assert_eq!(file.version, FrameFile::VERSION);
+        assert_eq!(file.metadata, Metadata::default());
+        assert_eq!(file.frame.head.position, "BA".parse().unwrap());
+        assert_eq!(file.frame.grid.get("AA".parse().unwrap()).as_str(), "abc");
+
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_version_zero() -> Result<(), FrameFileError> {
+        let file = FrameFile::from_value(json!({
+            "version": 0,
+            "metadata": { "title": "Zero" },
+            "frame": {
+                "head": { "position": "BA", "direction": "Down" },
+                "grid": { "AA": "abc" },
+                "stack": []
+            }
+        }))?;
+
+        assert_eq!(file.version, FrameFile::VERSION);
+        assert_eq!(file.metadata.title.as_deref(), Some("Zero"));
+        assert_eq!(file.frame.head.position, "BA".parse().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), FrameFileError> {
+        let file = FrameFile::new(
+            Frame::from_example("inst").unwrap(),
+            Metadata {
+                title: Some(String::from("Instructions")),
+                author: Some(String::from("Graliffer")),
+                description: None,
+            },
+        );
+
+        let parsed = FrameFile::from_json(&file.to_json())?;
+
+        assert_eq!(parsed.metadata, file.metadata);
+        assert_eq!(
+            serde_json::to_value(&parsed.frame).unwrap(),
+            serde_json::to_value(&file.frame).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let err = FrameFile::from_value(json!({
+            "version": 1,
+            "frame": {
+                "head": { "position": "A-", "direction": "Right" },
+                "grid": {},
+                "stack": []
+            }
+        }))
+        .unwrap_err();
+        match err {
+            FrameFileError::Deserialize(err) => {
+                assert_eq!(err.path().to_string(), "frame.head.position")
+            }
+            _ => panic!("expected a deserialize error, got {err}"),
+        }
+
+        let err = FrameFile::from_value(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": 5 },
+            "stack": []
+        }))
+        .unwrap_err();
+        match err {
+            FrameFileError::Deserialize(err) => {
+                assert_eq!(err.path().to_string(), "frame.grid.AA")
+            }
+            _ => panic!("expected a deserialize error, got {err}"),
+        }
+
+        assert!(matches!(
+            FrameFile::from_value(json!({ "version": 99 })),
+            Err(FrameFileError::UnsupportedVersion { got: 99 })
+        ));
+
+        assert!(matches!(
+            FrameFile::from_value(json!({ "version": "one" })),
+            Err(FrameFileError::InvalidVersion { .. })
+        ));
+
+        assert!(matches!(
+            FrameFile::from_value(json!([])),
+            Err(FrameFileError::Migration { from: 0, .. })
+        ));
+    }
+}
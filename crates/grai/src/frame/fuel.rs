@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Opcode, Word};
+
+/// How much fuel each part of a step consumes, see [`Fuel`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FuelCosts {
+    /// Cost of evaluating an opcode missing from `opcodes`, or a [custom](crate::CustomOpcode) one
+    pub opcode: u64,
+
+    /// Cost of evaluating specific opcodes
+    pub opcodes: HashMap<Opcode, u64>,
+
+    /// Cost of pushing an operand to the stack
+    pub operand: u64,
+
+    /// Cost of stepping over an empty cell
+    pub empty: u64,
+
+    /// Cost of each cell read while resolving an address or a pointer, a chain of pointers costs
+    /// one read per pointer followed
+    pub read: u64,
+}
+
+impl Default for FuelCosts {
+    fn default() -> Self {
+        Self {
+            opcode: 1,
+            opcodes: HashMap::new(),
+            operand: 1,
+            empty: 1,
+            read: 1,
+        }
+    }
+}
+
+impl FuelCosts {
+    /// Cost of executing `word`, `None` being an empty cell, not counting reads
+    pub fn word(&self, word: Option<&Word>) -> u64 {
+        match word {
+            None => self.empty,
+            Some(Word::Operand(_)) => self.operand,
+            Some(Word::Opcode(opcode)) => self.opcodes.get(opcode).copied().unwrap_or(self.opcode),
+            Some(Word::Custom(_)) => self.opcode,
+        }
+    }
+}
+
+/// A limit on the work a [`Frame`](crate::Frame) can do
+///
+/// When set, each step consumes fuel according to its [`FuelCosts`]. A step whose word alone
+/// costs more fuel than remains is not applied, and fails with
+/// [`FrameError::OutOfFuel`](crate::FrameError), the execution can then go on after
+/// [refuelling](Fuel::refuel).
+///
+/// The cells read by a step are only known once it ran, and the step may have called host
+/// functions whose effects can't be undone. A step whose reads cost more than remains is thus
+/// kept, it drains the fuel, and the next step runs out of fuel.
+///
+/// Fuel is not given back when a step is reverted.
+///
+/// # Examples
+/// ```
+/// # use grai::{Frame, FrameError, Fuel};
+/// let mut frame = Frame::from_example("countdown").unwrap();
+/// frame.fuel = Some(Fuel::new(10));
+///
+/// let err = loop {
+///     if let Err(err) = frame.step() {
+///         break err;
+///     }
+/// };
+/// assert!(matches!(err, FrameError::OutOfFuel { .. }));
+///
+/// frame.fuel.as_mut().unwrap().refuel(100);
+/// assert!(frame.step().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fuel {
+    remaining: u64,
+
+    #[serde(default)]
+    pub costs: FuelCosts,
+}
+
+impl Fuel {
+    /// Obtain `Fuel` with `amount` remaining, using the default [`FuelCosts`]
+    pub fn new(amount: u64) -> Self {
+        Self::with_costs(amount, FuelCosts::default())
+    }
+
+    pub fn with_costs(amount: u64, costs: FuelCosts) -> Self {
+        Self {
+            remaining: amount,
+            costs,
+        }
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Add `amount` to the remaining fuel
+    pub fn refuel(&mut self, amount: u64) {
+        self.remaining = self.remaining.saturating_add(amount);
+    }
+
+    /// Consume `amount`, or all the remaining fuel if not enough remains
+    pub(crate) fn consume(&mut self, amount: u64) {
+        self.remaining = self.remaining.saturating_sub(amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{Frame, FrameError};
+
+    /// Pushes a pointer to an address, then adds what it resolves to
+    fn frame(fuel: u64) -> Frame {
+        let mut frame = Frame::from_text(
+            "A |&AC|1  |add|\n\
+             B |5  |\n\
+             C |@AB|",
+        )
+        .unwrap();
+
+        let costs = FuelCosts {
+            opcodes: HashMap::from([(Opcode::Add, 5)]),
+            operand: 2,
+            empty: 3,
+            read: 10,
+            ..Default::default()
+        };
+        frame.fuel = Some(Fuel::with_costs(fuel, costs));
+
+        frame
+    }
+
+    fn remaining(frame: &Frame) -> u64 {
+        frame.fuel.as_ref().unwrap().remaining()
+    }
+
+    #[test]
+    fn costs() {
+        let mut frame = frame(100);
+
+        let _ = frame.step().unwrap();
+        let _ = frame.step().unwrap();
+        assert_eq!(remaining(&frame), 96);
+
+        // Following the pointer, then the address, is two reads
+        let _ = frame.step().unwrap();
+        assert_eq!(remaining(&frame), 71);
+        assert_eq!(frame.stack.last().unwrap().to_string(), "6");
+
+        let _ = frame.step().unwrap();
+        assert_eq!(remaining(&frame), 68);
+    }
+
+    #[test]
+    fn out_of_fuel() {
+        let mut frame = frame(28);
+
+        let _ = frame.step().unwrap();
+        let _ = frame.step().unwrap();
+
+        // The reads make the step too costly, it is kept but drains the fuel
+        let _ = frame.step().unwrap();
+        assert_eq!(frame.stack.last().unwrap().to_string(), "6");
+        assert_eq!(remaining(&frame), 0);
+
+        let head = frame.head;
+        assert_eq!(
+            frame.step().unwrap_err(),
+            FrameError::OutOfFuel {
+                needed: 3,
+                remaining: 0
+            }
+        );
+        assert_eq!(frame.head, head);
+
+        frame.fuel.as_mut().unwrap().refuel(3);
+        let _ = frame.step().unwrap();
+        assert_eq!(remaining(&frame), 0);
+    }
+
+    #[test]
+    fn host_calls_run_once() {
+        let mut frame = Frame::from_text("A |@AB|cnt|cal|\nB |hey|").unwrap();
+        frame.fuel = Some(Fuel::new(3));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        frame
+            .functions
+            .register("cnt", 1, {
+                let calls = Arc::clone(&calls);
+                move |_| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    Ok(Vec::new())
+                }
+            })
+            .unwrap();
+
+        // Reading `hey` through `@AB` overdraws the fuel, the call is still applied only once
+        let err = loop {
+            if let Err(err) = frame.step() {
+                break err;
+            }
+        };
+        assert!(matches!(err, FrameError::OutOfFuel { .. }));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        frame.fuel.as_mut().unwrap().refuel(10);
+        let _ = frame.step().unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn serialize() {
+        let fuel: Fuel = serde_json::from_value(
+            json!({ "remaining": 10, "costs": { "opcodes": { "add": 5 } } }),
+        )
+        .unwrap();
+        assert_eq!(fuel.remaining(), 10);
+        assert_eq!(fuel.costs.word(Some(&Word::Opcode(Opcode::Add))), 5);
+        assert_eq!(fuel.costs.word(Some(&Word::Opcode(Opcode::Sub))), 1);
+
+        let frame = serde_json::to_value(Frame::default()).unwrap();
+        assert!(frame.get("fuel").is_none());
+    }
+}
@@ -1,4 +1,12 @@
-use std::{collections::HashMap, convert::Infallible};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    fmt::Debug,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 mod cell;
 use act::{Action, Revert, State};
@@ -9,41 +17,236 @@ pub use position::*;
 
 mod direction;
 pub use direction::*;
-use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Grid(HashMap<Position, Cell>);
+mod region;
+pub use region::*;
+
+mod query;
+pub use query::*;
+
+mod transform;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use transform::Transform;
+
+use crate::{Word, granary::GranaryDigit};
+
+/// Number of cells on each side of the grid
+const SIDE: usize = GranaryDigit::MAX_NUMERIC as usize + 1;
+
+/// The grid holding every [`Cell`] of a program
+///
+/// Cells are stored densely, row after row, along with the [`Word`] they decode to. Words are
+/// decoded the first time they are read, and decoded again only after the cell changes, so running
+/// the same code over and over does not parse its cells each time.
+pub struct Grid {
+    slots: Box<[Slot]>,
+
+    /// `true` while the reads of the interpreter are recorded in `reads`
+    recording: AtomicBool,
+    reads: Mutex<Reads>,
+}
+
+/// What the interpreter read from the grid while [recording](Grid::record_reads)
+#[derive(Debug, Default)]
+pub(crate) struct Reads {
+    /// Every position read, in order
+    pub positions: Vec<Position>,
+
+    /// Number of pointers followed by each pointer resolution, in order
+    pub chains: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Slot {
+    cell: Cell,
+
+    /// Decoded from `cell` on first read, reset whenever `cell` changes
+    word: OnceLock<Word>,
+}
+
+impl Slot {
+    fn set(&mut self, cell: Cell) {
+        self.cell = cell;
+        self.word.take();
+    }
+}
 
 impl Grid {
     /// Obtain a new empty `Grid`
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            slots: std::iter::repeat_with(Slot::default)
+                .take(SIDE * SIDE)
+                .collect(),
+            recording: AtomicBool::new(false),
+            reads: Mutex::new(Reads::default()),
+        }
+    }
+
+    fn index(position: Position) -> usize {
+        let (x, y) = position.as_numeric();
+        y as usize * SIDE + x as usize
+    }
+
+    fn position(index: usize) -> Position {
+        Position::from_numeric((index % SIDE) as u32, (index / SIDE) as u32)
+            .expect("a slot index should always be a valid position")
     }
 
     /// Insert given [`Cell`] at [`Position`]
     pub fn set(&mut self, position: Position, cell: Cell) {
-        if cell.is_empty() {
-            self.0.remove(&position);
-        } else {
-            self.0.insert(position, cell);
-        }
+        self.slots[Self::index(position)].set(cell);
     }
 
     /// Get the [`Cell`] at the given [`Position`]
     pub fn get(&self, position: Position) -> Cell {
-        if let Some(cell) = self.0.get(&position) {
-            cell.clone()
+        self.cell(position).clone()
+    }
+
+    fn cell(&self, position: Position) -> &Cell {
+        &self.slots[Self::index(position)].cell
+    }
+
+    /// Get the [`Cell`] at the given [`Position`], as the interpreter does when resolving an
+    /// address or a pointer, the read is recorded if [recording](Grid::record_reads)
+    pub(crate) fn read(&self, position: Position) -> Cell {
+        if self.recording.load(Ordering::Relaxed) {
+            self.lock_reads().positions.push(position);
+        }
+
+        self.get(position)
+    }
+
+    /// Record that a pointer resolution followed `pointers` pointers, if
+    /// [recording](Grid::record_reads)
+    pub(crate) fn followed(&self, pointers: usize) {
+        if self.recording.load(Ordering::Relaxed) {
+            self.lock_reads().chains.push(pointers);
+        }
+    }
+
+    /// Start recording the reads of the interpreter, see [`Grid::read()`] and [`Grid::followed()`]
+    pub(crate) fn record_reads(&self) {
+        self.recording.store(true, Ordering::Relaxed);
+    }
+
+    /// Stop recording reads, returning what was read since [`Grid::record_reads()`]
+    pub(crate) fn take_reads(&self) -> Reads {
+        self.recording.store(false, Ordering::Relaxed);
+        std::mem::take(&mut *self.lock_reads())
+    }
+
+    fn lock_reads(&self) -> std::sync::MutexGuard<'_, Reads> {
+        self.reads
+            .lock()
+            .expect("the reads lock should not be poisoned")
+    }
+
+    /// Get the [`Word`] the [`Cell`] at the given [`Position`] decodes to, `None` if the cell is
+    /// empty
+    pub fn word(&self, position: Position) -> Option<&Word> {
+        let slot = &self.slots[Self::index(position)];
+
+        if slot.cell.is_empty() {
+            None
         } else {
-            Cell::default()
+            Some(slot.word.get_or_init(|| Word::from_cell(slot.cell.clone())))
         }
     }
 
+    /// Iterate over every non-empty [`Cell`], row after row
+    pub fn cells(&self) -> impl Iterator<Item = (Position, &Cell)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| !slot.cell.is_empty())
+            .map(|(index, slot)| (Self::position(index), &slot.cell))
+    }
+
+    /// Iterate over every non-empty [`Cell`], column after column
+    pub fn cells_by_column(&self) -> impl Iterator<Item = (Position, &Cell)> {
+        (0..SIDE * SIDE)
+            .map(|index| (index % SIDE) * SIDE + index / SIDE)
+            .map(|index| (Self::position(index), &self.slots[index].cell))
+            .filter(|(_, cell)| !cell.is_empty())
+    }
+
+    /// Returns `true` if every cell is empty
+    pub fn is_empty(&self) -> bool {
+        self.cells().next().is_none()
+    }
+
+    /// The smallest [`Region`] holding every non-empty [`Cell`], `None` if the grid is empty
+    ///
+    /// # Examples
+    /// ```
+    /// # use grai::Frame;
+    /// let frame = Frame::from_text("B |   |1  |\nC |2  |").unwrap();
+    /// assert_eq!(frame.grid.bounding_box().unwrap().to_string(), "AB:BC");
+    /// ```
+    pub fn bounding_box(&self) -> Option<Region> {
+        let mut positions = self.cells().map(|(position, _)| position.as_numeric());
+        let (x, y) = positions.next()?;
+
+        let (left, top, right, bottom) =
+            positions.fold((x, y, x, y), |(left, top, right, bottom), (x, y)| {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            });
+
+        let position = |x, y| {
+            Position::from_numeric(x, y).expect("coordinates of cells should be valid positions")
+        };
+        Some(Region::new(position(left, top), position(right, bottom)))
+    }
+
+    /// View the cells of a rectangular [`Region`]
+    pub fn view(&self, region: Region) -> GridView<'_> {
+        GridView::new(self, region)
+    }
+
+    /// Iterate over the [`Position`] of every non-empty [`Cell`] matching `query`, row after row
+    pub fn find<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = Position> + 'a {
+        self.cells()
+            .filter(|(position, cell)| {
+                let word = self
+                    .word(*position)
+                    .expect("a non-empty cell should decode to a word");
+                query.matches(cell, word)
+            })
+            .map(|(position, _)| position)
+    }
+
     pub fn get_mut(&mut self, position: Position) -> &mut Cell {
-        self.0.entry(position).or_default()
-        // if let Some(cell) = self.0.get_mut(&position) {
-        //     cell
-        // } else {
-        // }
+        let slot = &mut self.slots[Self::index(position)];
+
+        // The cell may be changed through the returned reference, decode it again on next read
+        slot.word.take();
+        &mut slot.cell
+    }
+}
+
+impl Debug for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.cells()).finish()
+    }
+}
+
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.cells())
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cells = HashMap::<Position, Cell>::deserialize(deserializer)?;
+
+        let mut grid = Grid::new();
+        for (position, cell) in cells {
+            grid.set(position, cell);
+        }
+
+        Ok(grid)
     }
 }
 
@@ -53,25 +256,448 @@ impl Default for Grid {
     }
 }
 
+/// A change to the [`Grid`]
+///
+/// Actions on a [`Region`] are reverted in a single [`GridAction::Restore`]. Cells that would land
+/// outside of the grid are dropped.
+///
+/// # Examples
+/// ```
+/// # use act::State;
+/// # use grai::{Frame, GridAction, Rotation};
+/// let mut frame = Frame::from_text("A |1  |gri|@BA|").unwrap();
+///
+/// // Turn the row into a column, the direction and the address turn with it
+/// let _ = frame
+///     .act(GridAction::Rotate {
+///         region: "AA:CA".parse().unwrap(),
+///         rotation: Rotation::Clockwise,
+///         remap: true,
+///     })
+///     .unwrap();
+///
+/// let cell = |position: &str| frame.grid.get(position.parse().unwrap()).to_string();
+/// assert_eq!([cell("AA"), cell("AB"), cell("AC")], ["1", "gdo", "@AB"]);
+/// assert_eq!(cell("BA"), "");
+/// ```
 #[derive(Debug, Clone)]
 pub enum GridAction {
     Set(Position, Cell),
+
+    /// Set every cell of a region to the same cell
+    Fill(Region, Cell),
+
+    /// Empty every cell of a region
+    Clear(Region),
+
+    /// Copy the cells of `from`, its top-left corner landing on `to`
+    Copy {
+        from: Region,
+        to: Position,
+    },
+
+    /// Same as [`GridAction::Copy`], emptying the cells of `from` not overwritten by the copy
+    Move {
+        from: Region,
+        to: Position,
+    },
+
+    /// Rotate a region, the rotated cells keep the same top-left corner
+    ///
+    /// When `remap` is set, direction opcodes are turned, and addresses and pointers to a cell of
+    /// the region follow that cell.
+    Rotate {
+        region: Region,
+        rotation: Rotation,
+        remap: bool,
+    },
+
+    /// Mirror a region along `axis`, [horizontally](Axis::Horizontal) swapping its left and right
+    ///
+    /// When `remap` is set, direction opcodes are mirrored, and addresses and pointers to a cell of
+    /// the region follow that cell.
+    Mirror {
+        region: Region,
+        axis: Axis,
+        remap: bool,
+    },
+
+    /// Set each cell at its position, in order
+    Restore(Vec<(Position, Cell)>),
+}
+
+impl GridAction {
+    /// The cells set back by a revert of a grid action, made of [`GridAction::Set`] and
+    /// [`GridAction::Restore`] only
+    pub(crate) fn restored(&self) -> Vec<(Position, &Cell)> {
+        match self {
+            Self::Set(position, cell) => vec![(*position, cell)],
+            Self::Restore(cells) => cells
+                .iter()
+                .map(|(position, cell)| (*position, cell))
+                .collect(),
+            _ => unreachable!("a grid revert should only set or restore cells"),
+        }
+    }
 }
 
 impl Action for GridAction {}
 
+impl Grid {
+    /// Empty the cells at `cleared`, then set the cells of `written`, returns the prior content of
+    /// every position changed
+    fn rewrite(
+        &mut self,
+        cleared: Vec<Position>,
+        written: Vec<(Position, Cell)>,
+    ) -> Vec<(Position, Cell)> {
+        let mut seen = HashSet::new();
+        let prior = cleared
+            .iter()
+            .chain(written.iter().map(|(position, _)| position))
+            .filter(|position| seen.insert(**position))
+            .map(|position| (*position, self.get(*position)))
+            .collect();
+
+        for position in cleared {
+            self.set(position, Cell::default());
+        }
+        for (position, cell) in written {
+            self.set(position, cell);
+        }
+
+        prior
+    }
+
+    /// The cells of `from`, paired with where they land when its top-left corner moves to `to`
+    fn translated(&self, from: Region, to: Position) -> Vec<(Position, Cell)> {
+        let (left, top) = from.top_left().as_numeric();
+
+        from.positions()
+            .filter_map(|position| {
+                let target = Position::from_numeric(
+                    to.x() + position.x() - left,
+                    to.y() + position.y() - top,
+                );
+                target.ok().map(|target| (target, self.get(position)))
+            })
+            .collect()
+    }
+
+    /// The cells of `region`, paired with where they land once transformed
+    fn transformed(
+        &self,
+        region: Region,
+        transform: Transform,
+        remap: bool,
+    ) -> Vec<(Position, Cell)> {
+        region
+            .positions()
+            .filter_map(|position| {
+                let cell = self.get(position);
+                let cell = if remap {
+                    transform.remap(&region, cell)
+                } else {
+                    cell
+                };
+
+                transform
+                    .map(&region, position)
+                    .map(|target| (target, cell))
+            })
+            .collect()
+    }
+}
+
 impl State for Grid {
     type Action = GridAction;
     type Error = Infallible;
 
     fn act(&mut self, action: impl Into<Self::Action>) -> Result<Revert, Self::Error> {
-        match action.into() {
+        let (cleared, written) = match action.into() {
             GridAction::Set(position, cell) => {
                 let last_cell = self.get(position);
                 self.set(position, cell.clone());
 
-                Ok(Revert::new(GridAction::Set(position, last_cell)))
+                return Ok(Revert::new(GridAction::Set(position, last_cell)));
             }
+
+            GridAction::Fill(region, cell) => (
+                Vec::new(),
+                region
+                    .positions()
+                    .map(|position| (position, cell.clone()))
+                    .collect(),
+            ),
+            GridAction::Clear(region) => (region.positions().collect(), Vec::new()),
+            GridAction::Copy { from, to } => (Vec::new(), self.translated(from, to)),
+            GridAction::Move { from, to } => {
+                (from.positions().collect(), self.translated(from, to))
+            }
+            GridAction::Rotate {
+                region,
+                rotation,
+                remap,
+            } => (
+                region.positions().collect(),
+                self.transformed(region, Transform::Rotate(rotation), remap),
+            ),
+            GridAction::Mirror {
+                region,
+                axis,
+                remap,
+            } => (
+                region.positions().collect(),
+                self.transformed(region, Transform::Mirror(axis), remap),
+            ),
+            GridAction::Restore(cells) => (Vec::new(), cells),
+        };
+
+        let prior = self.rewrite(cleared, written);
+        Ok(Revert::new(GridAction::Restore(prior)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Opcode, Operand, OperandKind, WordKind};
+
+    #[test]
+    fn words_follow_cells() {
+        let mut grid = Grid::new();
+        let at = position("BC");
+        assert_eq!(grid.word(at), None);
+
+        grid.set(at, Cell::new_trim("add"));
+        assert_eq!(grid.word(at), Some(&Word::Opcode(Opcode::Add)));
+
+        let revert = grid
+            .act(GridAction::Set(at, Cell::new_trim("@AA")))
+            .unwrap();
+        assert_eq!(
+            grid.word(at),
+            Some(&Word::Operand(Operand::from_str("@AA").unwrap()))
+        );
+
+        for action in revert.into_actions() {
+            let action = *action.downcast::<GridAction>().unwrap();
+            let _ = grid.act(action).unwrap();
+        }
+        assert_eq!(grid.word(at), Some(&Word::Opcode(Opcode::Add)));
+
+        *grid.get_mut(at) = Cell::new_trim("1");
+        assert_eq!(
+            grid.word(at),
+            Some(&Word::Operand(Operand::from_str("1").unwrap()))
+        );
+
+        grid.set(at, Cell::default());
+        assert_eq!(grid.word(at), None);
+    }
+
+    #[test]
+    fn queries() {
+        let mut grid = Grid::new();
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounding_box(), None);
+
+        grid.set(position("CA"), Cell::new_trim("add"));
+        grid.set(position("AB"), Cell::new_trim("1"));
+        grid.set(position("BD"), Cell::new_trim("&CA"));
+
+        let order = |cells: Vec<(Position, &Cell)>| {
+            cells
+                .into_iter()
+                .map(|(position, _)| position.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(grid.cells().collect()), ["CA", "AB", "BD"]);
+        assert_eq!(order(grid.cells_by_column().collect()), ["AB", "BD", "CA"]);
+        assert_eq!(grid.bounding_box(), Some("AA:CD".parse().unwrap()));
+
+        let view = grid.view("AB:BD".parse().unwrap());
+        assert_eq!(order(view.cells().collect()), ["AB", "BD"]);
+        assert_eq!(view.get(position("AB")), Some(Cell::new_trim("1")));
+        assert_eq!(view.get(position("CA")), None);
+        assert_eq!(view.get_relative(1, 2), Some(Cell::new_trim("&CA")));
+        assert_eq!(view.get_relative(2, 0), None);
+        assert!(grid.view("DA:ZZ".parse().unwrap()).is_empty());
+
+        let found = |query: Query| {
+            grid.find(&query)
+                .map(|position| position.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found(Query::text("1")), ["AB"]);
+        assert_eq!(found(Query::text("ad")), Vec::<String>::new());
+        assert_eq!(found(Query::regex("a").unwrap()), ["CA"]);
+        assert_eq!(found(Query::kind(WordKind::Opcode)), ["CA"]);
+        assert_eq!(
+            found(Query::kind(WordKind::Operand(OperandKind::Literal))),
+            ["AB"]
+        );
+        assert!(Query::regex("(").is_err());
+    }
+
+    fn undo(grid: &mut Grid, revert: Revert) {
+        for action in revert.into_actions() {
+            let action = *action.downcast::<GridAction>().unwrap();
+            let _ = grid.act(action).unwrap();
+        }
+    }
+
+    fn rows(grid: &Grid, region: &str) -> Vec<String> {
+        let region: Region = region.parse().unwrap();
+        let view = grid.view(region);
+
+        (0..region.height())
+            .map(|y| {
+                (0..region.width())
+                    .map(|x| format!("{:3}", view.get_relative(x, y).unwrap().as_str()))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn region_actions() {
+        let mut grid = Grid::new();
+        grid.set(position("AA"), Cell::new_trim("a"));
+        grid.set(position("BA"), Cell::new_trim("b"));
+        grid.set(position("AB"), Cell::new_trim("c"));
+        let before = rows(&grid, "AA:CC");
+
+        let revert = grid
+            .act(GridAction::Copy {
+                from: "AA:BB".parse().unwrap(),
+                to: position("BB"),
+            })
+            .unwrap();
+        assert_eq!(
+            rows(&grid, "AA:CC"),
+            ["a  |b  |   ", "c  |a  |b  ", "   |c  |   "]
+        );
+        undo(&mut grid, revert);
+        assert_eq!(rows(&grid, "AA:CC"), before);
+
+        let revert = grid
+            .act(GridAction::Move {
+                from: "AA:BB".parse().unwrap(),
+                to: position("BA"),
+            })
+            .unwrap();
+        assert_eq!(
+            rows(&grid, "AA:CC"),
+            ["   |a  |b  ", "   |c  |   ", "   |   |   "]
+        );
+        undo(&mut grid, revert);
+        assert_eq!(rows(&grid, "AA:CC"), before);
+
+        let revert = grid
+            .act(GridAction::Fill(
+                "BB:CC".parse().unwrap(),
+                Cell::new_trim("x"),
+            ))
+            .unwrap();
+        let revert_clear = grid
+            .act(GridAction::Clear("AA:BA".parse().unwrap()))
+            .unwrap();
+        assert_eq!(
+            rows(&grid, "AA:CC"),
+            ["   |   |   ", "c  |x  |x  ", "   |x  |x  "]
+        );
+        undo(&mut grid, revert_clear);
+        undo(&mut grid, revert);
+        assert_eq!(rows(&grid, "AA:CC"), before);
+
+        let revert = grid
+            .act(GridAction::Rotate {
+                region: "AA:BB".parse().unwrap(),
+                rotation: Rotation::Clockwise,
+                remap: false,
+            })
+            .unwrap();
+        assert_eq!(rows(&grid, "AA:BB"), ["c  |a  ", "   |b  "]);
+        undo(&mut grid, revert);
+
+        let revert = grid
+            .act(GridAction::Mirror {
+                region: "AA:BB".parse().unwrap(),
+                axis: Axis::Vertical,
+                remap: false,
+            })
+            .unwrap();
+        assert_eq!(rows(&grid, "AA:BB"), ["c  |   ", "a  |b  "]);
+        undo(&mut grid, revert);
+        assert_eq!(rows(&grid, "AA:CC"), before);
+
+        // Cells landing outside of the grid are dropped
+        let _ = grid
+            .act(GridAction::Copy {
+                from: "AA:BA".parse().unwrap(),
+                to: position("/A"),
+            })
+            .unwrap();
+        assert_eq!(grid.get(position("/A")).as_str(), "a");
+    }
+
+    #[test]
+    fn remap() {
+        let mut grid = Grid::new();
+        for (at, cell) in [
+            ("AA", "gri"),
+            ("BA", "@AA"),
+            ("CA", "&BA"),
+            ("AB", "@ZZ"),
+            ("BB", "gup"),
+        ] {
+            grid.set(position(at), Cell::new_trim(cell));
         }
+
+        let mut mirrored = Grid::new();
+        for (position, cell) in grid.cells() {
+            mirrored.set(position, cell.clone());
+        }
+        let _ = mirrored
+            .act(GridAction::Mirror {
+                region: "AA:CB".parse().unwrap(),
+                axis: Axis::Horizontal,
+                remap: true,
+            })
+            .unwrap();
+        assert_eq!(rows(&mirrored, "AA:CB"), ["&BA|@CA|gle", "   |gup|@ZZ"]);
+
+        let _ = grid
+            .act(GridAction::Rotate {
+                region: "AA:CB".parse().unwrap(),
+                rotation: Rotation::HalfTurn,
+                remap: true,
+            })
+            .unwrap();
+        assert_eq!(rows(&grid, "AA:CB"), ["   |gdo|@ZZ", "&BB|@CB|gle"]);
+    }
+
+    #[test]
+    fn cells_and_serde() {
+        let mut grid = Grid::new();
+        grid.set(position("//"), Cell::new_trim("b"));
+        grid.set(position("BA"), Cell::new_trim("a"));
+        grid.set(position("AB"), Cell::new_trim(""));
+
+        assert_eq!(
+            grid.cells()
+                .map(|(position, cell)| format!("{position}={cell}"))
+                .collect::<Vec<_>>(),
+            ["BA=a", "//=b"]
+        );
+
+        let json = serde_json::to_value(&grid).unwrap();
+        assert_eq!(json, serde_json::json!({ "BA": "a", "//": "b" }));
+
+        let grid: Grid = serde_json::from_value(json).unwrap();
+        assert_eq!(grid.get(position("//")).as_str(), "b");
+        assert_eq!(grid.cells().count(), 2);
     }
 }
@@ -14,7 +14,19 @@ pub enum VerticalDirection {
     Down,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum_macros::EnumString,
+    strum_macros::Display,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
 pub enum Direction {
     Up,
     Right,
@@ -29,6 +41,48 @@ impl Direction {
             Self::Right | Self::Left => Axis::Horizontal,
         }
     }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Right => Self::Left,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+        }
+    }
+
+    /// The direction after a [`Rotation`]
+    pub fn rotated(&self, rotation: Rotation) -> Self {
+        let clockwise = |direction: Self| match direction {
+            Self::Up => Self::Right,
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+            Self::Left => Self::Up,
+        };
+
+        match rotation {
+            Rotation::Clockwise => clockwise(*self),
+            Rotation::HalfTurn => self.opposite(),
+            Rotation::Counterclockwise => clockwise(*self).opposite(),
+        }
+    }
+
+    /// The direction after mirroring along `axis`, reversed if it is on that axis
+    pub fn mirrored(&self, axis: Axis) -> Self {
+        if self.axis() == axis {
+            self.opposite()
+        } else {
+            *self
+        }
+    }
+}
+
+/// A rotation by a quarter turn or a half turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rotation {
+    Clockwise,
+    HalfTurn,
+    Counterclockwise,
 }
 
 impl From<VerticalDirection> for Direction {
@@ -49,7 +103,7 @@ impl From<HorizontalDirection> for Direction {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Axis {
     Horizontal,
     Vertical,
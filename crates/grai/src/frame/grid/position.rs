@@ -495,3 +495,9 @@ impl Display for Position {
         f.write_str(&self.as_textual_string())
     }
 }
+
+/// Parse a [`Position`] in textual form, for tests
+#[cfg(test)]
+pub(crate) fn position(position: &str) -> Position {
+    position.parse().expect("test positions should be valid")
+}
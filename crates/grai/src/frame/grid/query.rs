@@ -0,0 +1,107 @@
+use regex::Regex;
+
+use crate::{Cell, Grid, Position, Region, Word, WordKind};
+
+/// What to look for in a [`Grid`], see [`Grid::find()`]
+///
+/// # Examples
+/// ```
+/// # use grai::{Frame, OperandKind, Query, WordKind};
+/// let frame = Frame::from_text("A |1  |@AB|jmp|\nB |add|&AA|").unwrap();
+///
+/// let found = |query| {
+///     frame.grid.find(&query).map(|position| position.to_string()).collect::<Vec<_>>()
+/// };
+/// assert_eq!(found(Query::text("add")), ["AB"]);
+/// assert_eq!(found(Query::regex("^[@&]").unwrap()), ["BA", "BB"]);
+/// assert_eq!(found(Query::kind(WordKind::Opcode)), ["CA", "AB"]);
+/// assert_eq!(found(Query::kind(WordKind::Operand(OperandKind::Pointer))), ["BB"]);
+/// ```
+#[derive(Debug, Clone)]
+pub enum Query {
+    /// Cells holding exactly this text
+    Text(String),
+
+    /// Cells whose text matches this regular expression
+    Regex(Regex),
+
+    /// Cells decoding to a [`Word`] of this kind
+    Kind(WordKind),
+}
+
+impl Query {
+    pub fn text(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+
+    /// # Errors
+    /// Returns an error if `pattern` is not a valid regular expression
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self::Regex(Regex::new(pattern)?))
+    }
+
+    pub fn kind(kind: WordKind) -> Self {
+        Self::Kind(kind)
+    }
+
+    /// Returns `true` if a non-empty `cell`, decoding to `word`, matches the query
+    pub fn matches(&self, cell: &Cell, word: &Word) -> bool {
+        match self {
+            Self::Text(text) => cell.as_str() == text,
+            Self::Regex(regex) => regex.is_match(cell.as_str()),
+            Self::Kind(kind) => word.kind() == *kind,
+        }
+    }
+}
+
+/// A rectangular [`Region`] of a [`Grid`], see [`Grid::view()`]
+#[derive(Debug, Clone, Copy)]
+pub struct GridView<'a> {
+    grid: &'a Grid,
+    region: Region,
+}
+
+impl<'a> GridView<'a> {
+    pub(crate) fn new(grid: &'a Grid, region: Region) -> Self {
+        Self { grid, region }
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Get the [`Cell`] at the given [`Position`], `None` if outside of the region
+    pub fn get(&self, position: Position) -> Option<Cell> {
+        self.region
+            .contains(position)
+            .then(|| self.grid.get(position))
+    }
+
+    /// Get the [`Cell`] at `x`, `y` from the top-left corner of the region, `None` if outside of
+    /// the region
+    pub fn get_relative(&self, x: u32, y: u32) -> Option<Cell> {
+        if x >= self.region.width() || y >= self.region.height() {
+            return None;
+        }
+
+        let (left, top) = self.region.top_left().as_numeric();
+        let position = Position::from_numeric(left + x, top + y)
+            .expect("a position inside a region should be valid");
+
+        Some(self.grid.get(position))
+    }
+
+    /// Iterate over every non-empty [`Cell`] of the region, row after row
+    pub fn cells(&self) -> impl Iterator<Item = (Position, &'a Cell)> + use<'a> {
+        let grid = self.grid;
+        self.region
+            .positions()
+            .map(move |position| (position, grid.cell(position)))
+            .filter(|(_, cell)| !cell.is_empty())
+    }
+
+    /// Returns `true` if every cell of the region is empty
+    pub fn is_empty(&self) -> bool {
+        self.cells().next().is_none()
+    }
+}
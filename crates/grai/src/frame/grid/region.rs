@@ -0,0 +1,140 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Position, PositionError};
+
+/// A rectangular area of the grid, bounds included
+///
+/// # Format
+/// A region is written `XY:XY`, its top-left and bottom-right corners, or `XY` for a single cell.
+///
+/// # Examples
+/// ```
+/// # use grai::{Position, Region};
+/// let region: Region = "BA:CB".parse().unwrap();
+/// assert!(region.contains("CA".parse().unwrap()));
+/// assert!(!region.contains("AA".parse().unwrap()));
+/// assert_eq!(region.positions().count(), 4);
+///
+/// // Corners can be given in any order
+/// let same: Region = "CB:BA".parse().unwrap();
+/// assert_eq!(region, same);
+///
+/// let cell: Region = "DD".parse().unwrap();
+/// assert_eq!(cell.to_string(), "DD");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
+pub struct Region {
+    top_left: Position,
+    bottom_right: Position,
+}
+
+impl Region {
+    /// Obtain the `Region` between two corners, in any order
+    pub fn new(corner: Position, opposite: Position) -> Self {
+        let (x1, y1) = corner.as_numeric();
+        let (x2, y2) = opposite.as_numeric();
+
+        let position = |x, y| {
+            Position::from_numeric(x, y).expect("coordinates of valid positions should be valid")
+        };
+
+        Self {
+            top_left: position(x1.min(x2), y1.min(y2)),
+            bottom_right: position(x1.max(x2), y1.max(y2)),
+        }
+    }
+
+    /// Obtain a `Region` holding a single cell
+    pub fn cell(position: Position) -> Self {
+        Self::new(position, position)
+    }
+
+    pub fn top_left(&self) -> Position {
+        self.top_left
+    }
+
+    pub fn bottom_right(&self) -> Position {
+        self.bottom_right
+    }
+
+    /// Number of columns of the region
+    pub fn width(&self) -> u32 {
+        self.bottom_right.x() - self.top_left.x() + 1
+    }
+
+    /// Number of rows of the region
+    pub fn height(&self) -> u32 {
+        self.bottom_right.y() - self.top_left.y() + 1
+    }
+
+    /// Returns `true` if `position` is inside the region
+    pub fn contains(&self, position: Position) -> bool {
+        (self.top_left.x()..=self.bottom_right.x()).contains(&position.x())
+            && (self.top_left.y()..=self.bottom_right.y()).contains(&position.y())
+    }
+
+    /// Returns `true` if the two regions share at least one cell
+    pub fn intersects(&self, other: &Region) -> bool {
+        self.top_left.x() <= other.bottom_right.x()
+            && other.top_left.x() <= self.bottom_right.x()
+            && self.top_left.y() <= other.bottom_right.y()
+            && other.top_left.y() <= self.bottom_right.y()
+    }
+
+    /// Iterate over every position of the region, row after row
+    pub fn positions(&self) -> impl Iterator<Item = Position> + use<> {
+        let (left, top) = self.top_left.as_numeric();
+        let (right, bottom) = self.bottom_right.as_numeric();
+
+        (top..=bottom).flat_map(move |y| {
+            (left..=right).map(move |x| {
+                Position::from_numeric(x, y).expect("positions of a region should be valid")
+            })
+        })
+    }
+}
+
+impl From<Position> for Region {
+    fn from(position: Position) -> Self {
+        Self::cell(position)
+    }
+}
+
+impl FromStr for Region {
+    type Err = PositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((corner, opposite)) => Ok(Self::new(corner.parse()?, opposite.parse()?)),
+            None => Ok(Self::cell(s.parse()?)),
+        }
+    }
+}
+
+impl TryFrom<String> for Region {
+    type Error = PositionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Region> for String {
+    fn from(value: Region) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.top_left == self.bottom_right {
+            write!(f, "{}", self.top_left)
+        } else {
+            write!(f, "{}:{}", self.top_left, self.bottom_right)
+        }
+    }
+}
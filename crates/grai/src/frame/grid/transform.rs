@@ -0,0 +1,79 @@
+use crate::{Address, Axis, Cell, Direction, Opcode, Pointer, Position, Region, Rotation};
+
+/// How the cells of a [`Region`] are rearranged, by [`GridAction::Rotate`](crate::GridAction) or
+/// [`GridAction::Mirror`](crate::GridAction)
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Transform {
+    Rotate(Rotation),
+    Mirror(Axis),
+}
+
+impl Transform {
+    /// Where the cell at `position`, inside `region`, lands, `None` if outside of the grid
+    ///
+    /// Rotated regions keep their top-left corner, a region of `w` by `h` cells rotated by a
+    /// quarter turn spans `h` by `w` cells.
+    pub fn map(&self, region: &Region, position: Position) -> Option<Position> {
+        let (left, top) = region.top_left().as_numeric();
+        let (width, height) = (region.width(), region.height());
+        let (x, y) = (position.x() - left, position.y() - top);
+
+        let (x, y) = match self {
+            Self::Rotate(Rotation::Clockwise) => (height - 1 - y, x),
+            Self::Rotate(Rotation::HalfTurn) => (width - 1 - x, height - 1 - y),
+            Self::Rotate(Rotation::Counterclockwise) => (y, width - 1 - x),
+            Self::Mirror(Axis::Horizontal) => (width - 1 - x, y),
+            Self::Mirror(Axis::Vertical) => (x, height - 1 - y),
+        };
+
+        Position::from_numeric(left + x, top + y).ok()
+    }
+
+    /// Rewrite `cell` so the code of `region` still works once transformed: direction opcodes are
+    /// turned, and addresses and pointers to a cell of `region` follow that cell
+    pub fn remap(&self, region: &Region, cell: Cell) -> Cell {
+        let target = |position: Position| {
+            region
+                .contains(position)
+                .then(|| self.map(region, position))
+                .flatten()
+        };
+
+        if let Ok(opcode) = Opcode::from_cell(cell.clone()) {
+            let direction = match opcode {
+                Opcode::Gup => Direction::Up,
+                Opcode::Gri => Direction::Right,
+                Opcode::Gdo => Direction::Down,
+                Opcode::Gle => Direction::Left,
+                _ => return cell,
+            };
+
+            let opcode = match self.direction(direction) {
+                Direction::Up => Opcode::Gup,
+                Direction::Right => Opcode::Gri,
+                Direction::Down => Opcode::Gdo,
+                Direction::Left => Opcode::Gle,
+            };
+            Cell::new_trim(&opcode.to_string())
+        } else if let Ok(address) = Address::from_ref_cell(&cell) {
+            match target(*address.position()) {
+                Some(position) => Address::from_position(position).to_cell(),
+                None => cell,
+            }
+        } else if let Ok(pointer) = Pointer::from_ref_cell(&cell) {
+            match target(*pointer.position()) {
+                Some(position) => Pointer::from_position(position).to_cell(),
+                None => cell,
+            }
+        } else {
+            cell
+        }
+    }
+
+    fn direction(&self, direction: Direction) -> Direction {
+        match self {
+            Self::Rotate(rotation) => direction.rotated(*rotation),
+            Self::Mirror(axis) => direction.mirrored(*axis),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{Direction, Position, PositionError};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Head {
     pub position: Position,
     pub direction: Direction,
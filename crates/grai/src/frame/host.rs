@@ -0,0 +1,206 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use crate::{Literal, LiteralFormatError, Operand};
+
+/// A Rust function callable from a program, see [`HostFunctions`]
+pub type HostFunction = dyn Fn(&[Operand]) -> Result<Vec<Operand>, String> + Send + Sync;
+
+/// Rust functions a [`Frame`](crate::Frame) can call with the `cal` opcode
+///
+/// A function is registered under a name, and takes a fixed number of arguments. To call it, a
+/// program pushes the arguments, then the name, and executes `cal` : the name and the arguments
+/// are popped, and what the function returns is pushed, in order.
+///
+/// Arguments are resolved as those of arithmetic opcodes, an address or a pointer is replaced by
+/// the literal it leads to. Functions are not part of the frame's state, undoing a `cal` brings the
+/// stack back, but does not undo what the function did.
+///
+/// # Examples
+/// ```
+/// # use grai::{Frame, Literal, Operand};
+/// let mut frame = Frame::from_text("A |20 |22 |sum|cal|").unwrap();
+/// frame
+///     .functions
+///     .register("sum", 2, |args| {
+///         let number = |operand: &Operand| match operand {
+///             Operand::Literal(literal) => literal.try_as_number().map_err(|err| err.to_string()),
+///             _ => Err("expected a number".to_string()),
+///         };
+///
+///         let sum = number(&args[0])? + number(&args[1])?;
+///         Ok(vec![Literal::from_number_trim(sum).into()])
+///     })
+///     .unwrap();
+///
+/// for _ in 0..4 {
+///     let _ = frame.step().unwrap();
+/// }
+/// assert_eq!(frame.stack.last().unwrap().to_string(), "42");
+/// ```
+#[derive(Clone, Default)]
+pub struct HostFunctions {
+    functions: HashMap<Literal, Function>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Function {
+    pub arity: usize,
+    pub function: Arc<HostFunction>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CallError {
+    #[error("the name of the function to call is errored")]
+    ErroredName,
+
+    #[error("no host function named `{0}`")]
+    UnknownFunction(Literal),
+
+    #[error("host function `{function}` failed : {message}")]
+    Failed { function: Literal, message: String },
+}
+
+impl HostFunctions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `function` under `name`, taking `arity` arguments, replacing any function
+    /// registered under the same name
+    ///
+    /// # Errors
+    /// Returns an error if `name` could not be written in a cell
+    pub fn register(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[Operand]) -> Result<Vec<Operand>, String> + Send + Sync + 'static,
+    ) -> Result<(), LiteralFormatError> {
+        let function = Function {
+            arity,
+            function: Arc::new(function),
+        };
+        self.functions.insert(Literal::from_str(name)?, function);
+
+        Ok(())
+    }
+
+    /// Unregister the function named `name`, returns `false` if there was none
+    pub fn unregister(&mut self, name: &str) -> bool {
+        Literal::from_str(name).is_ok_and(|name| self.functions.remove(&name).is_some())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        Literal::from_str(name).is_ok_and(|name| self.functions.contains_key(&name))
+    }
+
+    pub(crate) fn get(&self, name: &Literal) -> Option<Function> {
+        self.functions.get(name).cloned()
+    }
+}
+
+impl Debug for HostFunctions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set()
+            .entries(self.functions.keys().map(Literal::as_str))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{EvaluationError, Frame, FrameError};
+
+    fn literal(string: &str) -> Operand {
+        Literal::from_str(string).unwrap().into()
+    }
+
+    #[test]
+    fn call() {
+        let mut frame = Frame::from_text(
+            "A |abc|@AB|log|cal|out|cal|\n\
+             B |def|",
+        )
+        .unwrap();
+
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        frame
+            .functions
+            .register("log", 2, {
+                let logged = Arc::clone(&logged);
+                move |args| {
+                    logged
+                        .lock()
+                        .unwrap()
+                        .extend(args.iter().map(|arg| arg.to_string()));
+                    Ok(Vec::new())
+                }
+            })
+            .unwrap();
+        frame
+            .functions
+            .register("out", 0, |_| Ok(vec![literal("a"), literal("b")]))
+            .unwrap();
+        assert!(frame.functions.contains("out"));
+
+        let mut reverts = Vec::new();
+        for _ in 0..6 {
+            reverts.push(frame.step().unwrap());
+        }
+
+        // Arguments are given in the order they were pushed, resolved
+        assert_eq!(*logged.lock().unwrap(), ["abc", "def"]);
+        assert_eq!(
+            frame
+                .stack
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(frame.head.position, "GA".parse().unwrap());
+
+        for revert in reverts.into_iter().rev().take(2) {
+            frame.revert(revert).unwrap();
+        }
+        assert!(frame.stack.iter().next().is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let call = |text: &str| {
+            let mut frame = Frame::from_text(text).unwrap();
+            frame
+                .functions
+                .register("bad", 1, |_| Err("no luck".to_string()))
+                .unwrap();
+
+            loop {
+                if let Err(err) = frame.step() {
+                    break err;
+                }
+            }
+        };
+
+        let error = |err: CallError| FrameError::Evaluation(EvaluationError::Call(err));
+        let name = |name: &str| Literal::from_str(name).unwrap();
+
+        assert_eq!(
+            call("A |foo|cal|"),
+            error(CallError::UnknownFunction(name("foo")))
+        );
+        assert_eq!(
+            call("A |1  |bad|cal|"),
+            error(CallError::Failed {
+                function: name("bad"),
+                message: "no luck".to_string()
+            })
+        );
+        assert!(matches!(call("A |bad|cal|"), FrameError::FetchOperand(_)));
+
+        assert!(!HostFunctions::new().unregister("bad"));
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Debug;
+
+use crate::{Cell, Head, Operand, Position};
+
+/// A change applied to a [`Frame`](crate::Frame), as seen by an [`Observer`]
+///
+/// Every [`GridAction`](crate::GridAction), [`StackAction`](crate::StackAction) and
+/// [`HeadAction`](crate::HeadAction) applied to the frame produces an event, including the ones
+/// applied while stepping, or reverting. Grid actions produce an event per cell they change, cells
+/// left as they were are not reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameEvent {
+    /// A cell changed
+    Cell {
+        position: Position,
+        before: Cell,
+        after: Cell,
+    },
+
+    /// An operand was pushed on the stack, `depth` is the size of the stack after the push
+    Pushed { operand: Operand, depth: usize },
+
+    /// An operand was popped from the stack, `depth` is the size of the stack after the pop
+    Popped { operand: Operand, depth: usize },
+
+    /// The head moved or turned, or stayed in place when stepping outside of the grid
+    Head { before: Head, after: Head },
+}
+
+/// Notified of every [`FrameEvent`] of the [`Frame`](crate::Frame) it observes, see
+/// [`Frame::observe()`](crate::Frame::observe)
+///
+/// Any `FnMut(&FrameEvent)` closure is an observer.
+pub trait Observer: Send + Sync {
+    fn notify(&mut self, event: &FrameEvent);
+}
+
+impl<F: FnMut(&FrameEvent) + Send + Sync> Observer for F {
+    fn notify(&mut self, event: &FrameEvent) {
+        self(event)
+    }
+}
+
+/// Identifies an [`Observer`] of a [`Frame`](crate::Frame), to stop observing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+/// The observers of a [`Frame`](crate::Frame)
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_id: u64,
+    observers: Vec<(ObserverId, Box<dyn Observer>)>,
+}
+
+impl Observers {
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn add(&mut self, observer: impl Observer + 'static) -> ObserverId {
+        let id = ObserverId(self.next_id);
+        self.next_id += 1;
+
+        self.observers.push((id, Box::new(observer)));
+        id
+    }
+
+    pub fn remove(&mut self, id: ObserverId) -> bool {
+        let len = self.observers.len();
+        self.observers.retain(|(observer_id, _)| *observer_id != id);
+
+        self.observers.len() != len
+    }
+
+    pub fn notify(&mut self, event: &FrameEvent) {
+        for (_, observer) in &mut self.observers {
+            observer.notify(event);
+        }
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.observers.iter().map(|(id, _)| id))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use act::State;
+
+    use super::*;
+    use crate::{Direction, Frame, FrameAction, GridAction};
+
+    fn record(frame: &mut Frame) -> (ObserverId, Arc<Mutex<Vec<FrameEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let id = frame.observe({
+            let events = Arc::clone(&events);
+            move |event: &FrameEvent| events.lock().unwrap().push(event.clone())
+        });
+
+        (id, events)
+    }
+
+    fn head(position: &str, direction: Direction) -> Head {
+        Head::new(position.parse().unwrap(), direction)
+    }
+
+    #[test]
+    fn events() {
+        let mut frame = Frame::from_text("A |abc|@AB|set|gdo|").unwrap();
+        let (id, events) = record(&mut frame);
+
+        let mut reverts = Vec::new();
+        for _ in 0..4 {
+            reverts.push(frame.act(FrameAction::Step).unwrap());
+        }
+
+        let operand = |string: &str| Operand::from_str(string).unwrap();
+        let right = Direction::Right;
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                FrameEvent::Pushed {
+                    operand: operand("abc"),
+                    depth: 1
+                },
+                FrameEvent::Head {
+                    before: head("AA", right),
+                    after: head("BA", right)
+                },
+                FrameEvent::Pushed {
+                    operand: operand("@AB"),
+                    depth: 2
+                },
+                FrameEvent::Head {
+                    before: head("BA", right),
+                    after: head("CA", right)
+                },
+                FrameEvent::Popped {
+                    operand: operand("@AB"),
+                    depth: 1
+                },
+                FrameEvent::Popped {
+                    operand: operand("abc"),
+                    depth: 0
+                },
+                FrameEvent::Cell {
+                    position: "AB".parse().unwrap(),
+                    before: Cell::default(),
+                    after: Cell::new_trim("abc")
+                },
+                FrameEvent::Head {
+                    before: head("CA", right),
+                    after: head("DA", right)
+                },
+                FrameEvent::Head {
+                    before: head("DA", right),
+                    after: head("DA", Direction::Down)
+                },
+                FrameEvent::Head {
+                    before: head("DA", Direction::Down),
+                    after: head("DB", Direction::Down)
+                },
+            ]
+        );
+
+        // Reverting is observed too
+        events.lock().unwrap().clear();
+        for revert in reverts.into_iter().rev() {
+            frame.revert(revert).unwrap();
+        }
+        assert!(events.lock().unwrap().contains(&FrameEvent::Cell {
+            position: "AB".parse().unwrap(),
+            before: Cell::new_trim("abc"),
+            after: Cell::default(),
+        }));
+
+        events.lock().unwrap().clear();
+        assert!(frame.unobserve(id));
+        assert!(!frame.unobserve(id));
+
+        let _ = frame.act(FrameAction::Step).unwrap();
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unchanged_cells() {
+        let mut frame = Frame::from_text("A |abc|xyz|").unwrap();
+        let (_, events) = record(&mut frame);
+
+        let _ = frame
+            .act(GridAction::Fill(
+                "AA:CA".parse().unwrap(),
+                Cell::new_trim("abc"),
+            ))
+            .unwrap();
+        let _ = frame
+            .act(GridAction::Set(
+                "AA".parse().unwrap(),
+                Cell::new_trim("abc"),
+            ))
+            .unwrap();
+
+        let cell = |position: &str, before: &str| FrameEvent::Cell {
+            position: position.parse().unwrap(),
+            before: Cell::new_trim(before),
+            after: Cell::new_trim("abc"),
+        };
+        assert_eq!(*events.lock().unwrap(), [cell("BA", "xyz"), cell("CA", "")]);
+    }
+}
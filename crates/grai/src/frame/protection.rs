@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Position, Region};
+
+/// Regions of a [`Frame`](crate::Frame) the program can not write to
+///
+/// A `set` to a protected cell fails with a [`ProtectedError`], the
+/// [`ErrorPolicy`](crate::ErrorPolicy) of an [`Interpreter`](crate::Interpreter) then decides
+/// whether to stop or to skip it. Protections only apply to the program, the frame can still be
+/// changed through [`GridAction`](crate::GridAction)s, as editors do.
+///
+/// # Examples
+/// ```
+/// # use act::State;
+/// # use grai::{Frame, FrameAction};
+/// let mut frame = Frame::from_text("A |1  |@AA|set|").unwrap();
+/// let _ = frame.act(FrameAction::Protect("AA:CA".parse().unwrap())).unwrap();
+///
+/// let _ = frame.step().unwrap();
+/// let _ = frame.step().unwrap();
+/// assert!(frame.step().is_err());
+/// assert_eq!(frame.grid.get("AA".parse().unwrap()).as_str(), "1");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Protections {
+    regions: Vec<Region>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("can not set {position}, it is protected by {region}")]
+pub struct ProtectedError {
+    pub position: Position,
+    pub region: Region,
+}
+
+impl Protections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Every protected region, in the order they were protected
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Protect a region, returns `false` if it already was
+    pub fn protect(&mut self, region: Region) -> bool {
+        if self.regions.contains(&region) {
+            false
+        } else {
+            self.regions.push(region);
+            true
+        }
+    }
+
+    /// Stop protecting a region, returns `false` if it was not
+    pub fn unprotect(&mut self, region: Region) -> bool {
+        let len = self.regions.len();
+        self.regions.retain(|protected| *protected != region);
+
+        self.regions.len() != len
+    }
+
+    /// The first region protecting `position`, if any
+    pub fn protecting(&self, position: Position) -> Option<Region> {
+        self.regions
+            .iter()
+            .find(|region| region.contains(position))
+            .copied()
+    }
+
+    /// Check that the program can write at `position`
+    ///
+    /// # Errors
+    /// Returns an error if a region protects `position`
+    pub fn check(&self, position: Position) -> Result<(), ProtectedError> {
+        match self.protecting(position) {
+            Some(region) => Err(ProtectedError { position, region }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use act::State;
+
+    use super::*;
+    use crate::{
+        Break, Cell, ErrorPolicy, EvaluationError, Frame, FrameAction, FrameError, FrameFile,
+        GridAction, Interpreter, Outcome, position,
+    };
+
+    #[test]
+    fn protect() {
+        let mut protections = Protections::new();
+        let region: Region = "BB:CC".parse().unwrap();
+
+        assert!(protections.protect(region));
+        assert!(!protections.protect(region));
+        assert_eq!(protections.protecting(position("CB")), Some(region));
+        assert_eq!(protections.protecting(position("AA")), None);
+        assert_eq!(
+            protections.check(position("BC")),
+            Err(ProtectedError {
+                position: position("BC"),
+                region
+            })
+        );
+
+        assert!(protections.unprotect(region));
+        assert!(!protections.unprotect(region));
+        assert!(protections.is_empty());
+    }
+
+    #[test]
+    fn program_and_editor() {
+        let mut frame = Frame::from_text("A |x  |@AB|set|\nB |y  |").unwrap();
+        let revert = frame
+            .act(FrameAction::Protect("AB".parse().unwrap()))
+            .unwrap();
+
+        // The program can't write there, and the failed `set` keeps its operands
+        for _ in 0..2 {
+            let _ = frame.step().unwrap();
+        }
+        assert_eq!(
+            frame.step().unwrap_err(),
+            FrameError::Evaluation(EvaluationError::Protected(ProtectedError {
+                position: position("AB"),
+                region: "AB".parse().unwrap()
+            }))
+        );
+        assert_eq!(frame.stack.iter().len(), 2);
+        assert_eq!(frame.head.position, position("CA"));
+
+        // But the editor can
+        let _ = frame
+            .act(GridAction::Set(position("AB"), Cell::new_trim("z")))
+            .unwrap();
+        assert_eq!(frame.grid.get(position("AB")).as_str(), "z");
+
+        frame.revert(revert).unwrap();
+        assert!(frame.protections.is_empty());
+    }
+
+    #[test]
+    fn skipped_and_saved() {
+        let json = r#"{
+            "version": 1,
+            "frame": {
+                "head": { "position": "AA", "direction": "Right" },
+                "grid": { "AA": "x", "BA": "@AA", "CA": "set" },
+                "stack": [],
+                "protections": ["AA:CA"]
+            }
+        }"#;
+
+        let mut interpreter = Interpreter::builder()
+            .json(json)
+            .on_error(ErrorPolicy::Skip)
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::Halted));
+        assert_eq!(interpreter.errors().len(), 1);
+
+        let frame = interpreter.into_frame();
+        assert_eq!(frame.grid.get(position("AA")).as_str(), "x");
+
+        let file = FrameFile::from_json(json).unwrap();
+        let saved = serde_json::to_value(&file.frame).unwrap();
+        assert_eq!(saved["protections"], serde_json::json!(["AA:CA"]));
+    }
+}
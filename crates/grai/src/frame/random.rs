@@ -0,0 +1,185 @@
+use std::convert::Infallible;
+
+use act::{Action, Revert, State};
+use serde::{Deserialize, Serialize};
+
+/// The pseudo-random number generator of a [`Frame`](crate::Frame), used by `rnd`
+///
+/// The generator is a [SplitMix64](https://prng.di.unimi.it/splitmix64.c), its whole state is a
+/// single number, saved along the frame. Two frames with the same state draw the same numbers,
+/// and since drawing is a [`RandomAction`], reverting a step also rewinds the generator.
+///
+/// # Examples
+/// ```
+/// # use grai::{Frame, Random};
+/// let mut frame = Frame::from_text("A |1  |6  |rnd|").unwrap();
+/// frame.random = Random::new(42);
+///
+/// let mut replay = Frame::from_text("A |1  |6  |rnd|").unwrap();
+/// replay.random = Random::new(42);
+///
+/// for _ in 0..3 {
+///     let _ = frame.step().unwrap();
+///     let _ = replay.step().unwrap();
+/// }
+/// assert_eq!(frame.stack.last(), replay.stack.last());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns `true` if the generator is in the state of a frame that was never seeded
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The current state of the generator, a `Random` created with it as seed draws the same
+    /// numbers from now on
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// The next number, and the state of the generator after drawing it, without changing `self`
+    pub fn peek(&self) -> (u64, u64) {
+        let state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        (z ^ (z >> 31), state)
+    }
+
+    /// Draw a number in `min..=max`, `None` if the range is empty
+    pub fn range(&mut self, min: u32, max: u32) -> Option<u32> {
+        let (value, state) = self.peek_range(min, max)?;
+        self.state = state;
+
+        Some(value)
+    }
+
+    /// Same as [`Random::peek()`], for a number in `min..=max`, `None` if the range is empty
+    pub fn peek_range(&self, min: u32, max: u32) -> Option<(u32, u64)> {
+        if min > max {
+            return None;
+        }
+
+        let (value, state) = self.peek();
+        let span = (max - min) as u64 + 1;
+
+        Some((min + (value % span) as u32, state))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RandomAction {
+    /// Set the state of the generator
+    Seed(u64),
+}
+
+impl Action for RandomAction {}
+
+impl State for Random {
+    type Action = RandomAction;
+    type Error = Infallible;
+
+    fn act(&mut self, action: impl Into<Self::Action>) -> Result<Revert, Self::Error> {
+        match action.into() {
+            RandomAction::Seed(state) => {
+                let last_state = self.state;
+                self.state = state;
+
+                Ok(Revert::new(RandomAction::Seed(last_state)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use act::State;
+
+    use super::*;
+    use crate::{Frame, Operand};
+
+    #[test]
+    fn range() {
+        let mut random = Random::new(7);
+        let drawn: Vec<u32> = (0..100).map(|_| random.range(3, 5).unwrap()).collect();
+
+        assert!(drawn.iter().all(|value| (3..=5).contains(value)));
+        assert!((3..=5).all(|value| drawn.contains(&value)));
+
+        assert_eq!(random.range(4, 4), Some(4));
+        assert_eq!(random.range(5, 4), None);
+
+        let mut replay = Random::new(7);
+        let replayed: Vec<u32> = (0..100).map(|_| replay.range(3, 5).unwrap()).collect();
+        assert_eq!(drawn, replayed);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut frame = Frame::from_text("A |0  |999|rnd|0  |999|rnd|").unwrap();
+        frame.random = Random::new(1);
+
+        let mut reverts = Vec::new();
+        for _ in 0..6 {
+            reverts.push(frame.step().unwrap());
+        }
+        let drawn = frame.stack.last().cloned();
+
+        // Undo the last `rnd` and its operands, drawing again gives the same number
+        for revert in reverts.drain(3..).rev() {
+            frame.revert(revert).unwrap();
+        }
+        for _ in 0..3 {
+            let _ = frame.step().unwrap();
+        }
+        assert_eq!(frame.stack.last().cloned(), drawn);
+
+        // An empty range is errored, without drawing
+        let mut frame = Frame::from_text("A |2  |1  |rnd|").unwrap();
+        let state = frame.random.state();
+        for _ in 0..3 {
+            let _ = frame.step().unwrap();
+        }
+        assert!(matches!(frame.stack.last(), Some(Operand::Errored(_))));
+        assert_eq!(frame.random.state(), state);
+
+        let mut random = Random::default();
+        let revert = random.act(RandomAction::Seed(5)).unwrap();
+        assert_eq!(random.state(), 5);
+
+        for action in revert.into_actions() {
+            let action = action.downcast_ref::<RandomAction>().unwrap().clone();
+            let _ = random.act(action).unwrap();
+        }
+        assert_eq!(random, Random::default());
+    }
+
+    #[test]
+    fn serialize() {
+        let mut frame = Frame::from_text("A |rnd|").unwrap();
+        assert!(
+            serde_json::to_value(&frame)
+                .unwrap()
+                .get("random")
+                .is_none()
+        );
+
+        frame.random = Random::new(42);
+        let json = serde_json::to_value(&frame).unwrap();
+        assert_eq!(json["random"], 42);
+
+        let read: Frame = serde_json::from_value(json).unwrap();
+        assert_eq!(read.random, frame.random);
+    }
+}
@@ -0,0 +1,221 @@
+//! A compact binary encoding of a whole [`Frame`], meant for frequent checkpoints
+//!
+//! # Format
+//! A snapshot starts with a fixed size header, followed by the encoded frame :
+//! - 4 bytes, the magic number `GRAF`
+//! - 2 bytes, the format version, as a little endian `u16`
+//! - 4 bytes, the length of the encoded frame, as a little endian `u32`
+//! - the frame, encoded as [MessagePack](https://msgpack.org) using the same representation as
+//!   the JSON form
+//!
+//! As the length of the snapshot is known from its header, snapshots can be embedded in other
+//! files, and read back without consuming anything past their end.
+
+use std::io::{self, Read, Write};
+
+use crate::Frame;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("io error : {0}")]
+    Io(#[from] io::Error),
+
+    #[error("not a frame snapshot, invalid magic number `{got:?}`")]
+    InvalidMagic { got: [u8; 4] },
+
+    #[error(
+        "unsupported snapshot version `{got}`, latest supported is `{}`",
+        Snapshot::VERSION
+    )]
+    UnsupportedVersion { got: u16 },
+
+    #[error("frame is too large to be snapshotted")]
+    TooLarge,
+
+    #[error("could not encode frame : {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+
+    #[error("could not decode frame : {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+/// Namespace for the snapshot format constants
+pub struct Snapshot;
+
+impl Snapshot {
+    pub const MAGIC: [u8; 4] = *b"GRAF";
+    pub const VERSION: u16 = 1;
+
+    const HEADER_LEN: usize = 10;
+}
+
+impl Frame {
+    /// Encode `self` as a binary snapshot, see the [`snapshot`](crate::snapshot) module for the
+    /// format
+    ///
+    /// # Examples
+    /// ```
+    /// # use grai::Frame;
+    /// let frame = Frame::from_example("inst").unwrap();
+    /// let snapshot = frame.to_snapshot().unwrap();
+    ///
+    /// let restored = Frame::from_snapshot(&snapshot).unwrap();
+    /// assert_eq!(restored.head.position, frame.head.position);
+    /// ```
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = Vec::new();
+        self.write_snapshot(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    /// Write `self` as a binary snapshot to the given writer
+    pub fn write_snapshot(&self, writer: &mut impl Write) -> Result<(), SnapshotError> {
+        let body = rmp_serde::to_vec_named(self)?;
+        let body_len = u32::try_from(body.len()).map_err(|_| SnapshotError::TooLarge)?;
+
+        let mut header = [0; Snapshot::HEADER_LEN];
+        header[0..4].copy_from_slice(&Snapshot::MAGIC);
+        header[4..6].copy_from_slice(&Snapshot::VERSION.to_le_bytes());
+        header[6..10].copy_from_slice(&body_len.to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(&body)?;
+
+        Ok(())
+    }
+
+    /// Decode a `Frame` from a binary snapshot
+    ///
+    /// # Errors
+    /// Returns an error if the bytes are not a valid snapshot, or if the snapshot is followed by
+    /// unexpected bytes, use [`Frame::read_snapshot()`] to read snapshots embedded in other data
+    pub fn from_snapshot(mut bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let frame = Self::read_snapshot(&mut bytes)?;
+
+        if bytes.is_empty() {
+            Ok(frame)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected trailing bytes after snapshot",
+            )
+            .into())
+        }
+    }
+
+    /// Read a `Frame` from a binary snapshot, consuming exactly the snapshot bytes from the reader
+    pub fn read_snapshot(reader: &mut impl Read) -> Result<Self, SnapshotError> {
+        let mut header = [0; Snapshot::HEADER_LEN];
+        reader.read_exact(&mut header)?;
+
+        let magic: [u8; 4] = header[0..4].try_into().expect("slice is 4 bytes long");
+        if magic != Snapshot::MAGIC {
+            return Err(SnapshotError::InvalidMagic { got: magic });
+        }
+
+        let version = u16::from_le_bytes(header[4..6].try_into().expect("slice is 2 bytes long"));
+        if version != Snapshot::VERSION {
+            return Err(SnapshotError::UnsupportedVersion { got: version });
+        }
+
+        let body_len = u32::from_le_bytes(header[6..10].try_into().expect("slice is 4 bytes long"));
+
+        let mut body = Vec::new();
+        reader.take(body_len.into()).read_to_end(&mut body)?;
+        if body.len() != body_len as usize {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(rmp_serde::from_slice(&body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use act::State;
+
+    use super::*;
+    use crate::{FrameAction, Position, examples};
+
+    fn as_json(frame: &Frame) -> serde_json::Value {
+        serde_json::to_value(frame).unwrap()
+    }
+
+    fn assert_round_trip(frame: &Frame) {
+        let snapshot = frame.to_snapshot().unwrap();
+        let restored = Frame::from_snapshot(&snapshot).unwrap();
+
+        assert_eq!(as_json(&restored), as_json(frame));
+    }
+
+    #[test]
+    fn round_trip_examples() {
+        for name in examples::names() {
+            let frame = Frame::from_example(name).unwrap();
+            assert_round_trip(&frame);
+
+            let json_len = serde_json::to_vec(&frame).unwrap().len();
+            assert!(frame.to_snapshot().unwrap().len() < json_len);
+        }
+    }
+
+    #[test]
+    fn round_trip_running() {
+        let mut frame = Frame::from_example("getting_started").unwrap();
+        let _ = frame
+            .act(FrameAction::SetBreakpoint(
+                Position::from_numeric(3, 0).unwrap(),
+            ))
+            .unwrap();
+
+        for _ in 0..10 {
+            let _ = frame.act(FrameAction::Step).unwrap();
+            assert_round_trip(&frame);
+        }
+    }
+
+    #[test]
+    fn embedded() {
+        let inst = Frame::from_example("inst").unwrap();
+        let looping = Frame::from_example("loop").unwrap();
+
+        let mut bytes = b"prefix".to_vec();
+        inst.write_snapshot(&mut bytes).unwrap();
+        looping.write_snapshot(&mut bytes).unwrap();
+        bytes.extend_from_slice(b"suffix");
+
+        let mut reader = &bytes[6..];
+        assert_eq!(
+            as_json(&Frame::read_snapshot(&mut reader).unwrap()),
+            as_json(&inst)
+        );
+        assert_eq!(
+            as_json(&Frame::read_snapshot(&mut reader).unwrap()),
+            as_json(&looping)
+        );
+        assert_eq!(reader, b"suffix");
+    }
+
+    #[test]
+    fn invalid() {
+        let mut snapshot = Frame::from_example("inst").unwrap().to_snapshot().unwrap();
+
+        assert!(matches!(
+            Frame::from_snapshot(&snapshot[..snapshot.len() - 1]),
+            Err(SnapshotError::Io(_))
+        ));
+
+        snapshot[4] = 0xff;
+        assert!(matches!(
+            Frame::from_snapshot(&snapshot),
+            Err(SnapshotError::UnsupportedVersion { got: 0xff })
+        ));
+
+        snapshot[0] = b'X';
+        assert!(matches!(
+            Frame::from_snapshot(&snapshot),
+            Err(SnapshotError::InvalidMagic { .. })
+        ));
+    }
+}
@@ -1,3 +1,5 @@
+use std::slice::Iter;
+
 use act::{Action, Revert, State};
 use serde::{Deserialize, Serialize};
 
@@ -31,9 +33,17 @@ impl Stack {
         self.0.last()
     }
 
-    // pub fn iter(&self) -> Iter<'_, Operand> {
-    //     self.0.iter()
-    // }
+    /// Iterate over the operands, from the bottom to the top of the stack
+    pub fn iter(&self) -> Iter<'_, Operand> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Operand> for Stack {
+    /// Obtain a `Stack` from operands, the last one being on top
+    fn from_iter<T: IntoIterator<Item = Operand>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+
+use act::{Revert, State};
+
+use crate::{Frame, FrameAction, FrameError};
+
+/// `History` records the [`Revert`] of each step of a [`Frame`], allowing to step back the
+/// execution, one step at a time, or up to a breakpoint
+///
+/// # Examples
+/// ```
+/// # use grai::{Frame, History};
+/// let mut frame = Frame::from_example("inst").unwrap();
+/// let mut history = History::new();
+///
+/// history.step(&mut frame).unwrap();
+/// history.step(&mut frame).unwrap();
+/// assert_eq!(history.len(), 2);
+///
+/// assert_eq!(history.step_back_n(&mut frame, 5).unwrap(), 2);
+/// assert_eq!(frame.head.position.as_textual_string(), "AA");
+/// assert!(history.is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct History {
+    steps: VecDeque<Revert>,
+    limit: Option<usize>,
+}
+
+impl History {
+    /// Obtain a new empty `History`, without limit on the number of recorded steps
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Obtain a new empty `History`, that will only keep the last `limit` recorded steps
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            steps: VecDeque::new(),
+            limit: Some(limit),
+        }
+    }
+
+    /// Return the number of steps that can be stepped back
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Forget every recorded steps
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    /// Record the [`Revert`] of a step, returned by [`Frame::step()`]
+    ///
+    /// If the `History` has a limit, the oldest step is forgotten when the limit is reached
+    pub fn record(&mut self, revert: Revert) {
+        if self.limit == Some(0) {
+            return;
+        }
+
+        if let Some(limit) = self.limit
+            && self.steps.len() >= limit
+        {
+            self.steps.pop_front();
+        }
+
+        self.steps.push_back(revert);
+    }
+
+    /// Step the [`Frame`] and record the step
+    ///
+    /// # Errors
+    /// Returns an error if the step could not be performed, nothing is recorded in that case
+    pub fn step(&mut self, frame: &mut Frame) -> Result<(), FrameError> {
+        let revert = frame.act(FrameAction::Step)?;
+        self.record(revert);
+
+        Ok(())
+    }
+
+    /// Revert the last recorded step, returns `false` if there was nothing to step back
+    ///
+    /// # Errors
+    /// Returns an error if the step could not be reverted, the step is forgotten in that case
+    pub fn step_back(&mut self, frame: &mut Frame) -> Result<bool, FrameError> {
+        match self.steps.pop_back() {
+            Some(revert) => frame.revert(revert).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Revert up to `count` recorded steps, returns the number of steps actually stepped back
+    ///
+    /// # Errors
+    /// Returns an error if a step could not be reverted, stopping there
+    pub fn step_back_n(&mut self, frame: &mut Frame, count: usize) -> Result<usize, FrameError> {
+        let mut stepped = 0;
+
+        while stepped < count && self.step_back(frame)? {
+            stepped += 1;
+        }
+
+        Ok(stepped)
+    }
+
+    /// Revert recorded steps until the head lands on a breakpoint or the history is exhausted,
+    /// returns the number of steps stepped back
+    ///
+    /// At least one step is stepped back, so that successive calls go from one breakpoint to the
+    /// previous one
+    ///
+    /// # Errors
+    /// Returns an error if a step could not be reverted, stopping there
+    pub fn step_back_to_breakpoint(&mut self, frame: &mut Frame) -> Result<usize, FrameError> {
+        let mut stepped = 0;
+
+        while self.step_back(frame)? {
+            stepped += 1;
+
+            if frame.debug.has_breakpoint(frame.head.position) {
+                break;
+            }
+        }
+
+        Ok(stepped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Position;
+
+    fn create_frame(json: serde_json::Value) -> Frame {
+        serde_json::from_value(json).expect("must be a valid frame")
+    }
+
+    fn snapshot(frame: &Frame) -> serde_json::Value {
+        serde_json::to_value(frame).unwrap()
+    }
+
+    #[test]
+    fn step_back() -> Result<(), FrameError> {
+        let mut frame = create_frame(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "abc", "BA": "@DA", "CA": "set" },
+            "stack": []
+        }));
+        let mut history = History::new();
+
+        let mut snapshots = vec![snapshot(&frame)];
+        for _ in 0..3 {
+            history.step(&mut frame)?;
+            snapshots.push(snapshot(&frame));
+        }
+        assert_eq!(frame.grid.get("DA".parse().unwrap()).as_str(), "abc");
+
+        while let Some(expected) = snapshots.pop() {
+            assert_eq!(snapshot(&frame), expected);
+            history.step_back(&mut frame)?;
+        }
+
+        assert!(!history.step_back(&mut frame)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn step_back_n() -> Result<(), FrameError> {
+        let mut frame = Frame::from_example("inst").unwrap();
+        let initial = snapshot(&frame);
+        let mut history = History::new();
+
+        for _ in 0..4 {
+            history.step(&mut frame)?;
+        }
+
+        assert_eq!(history.step_back_n(&mut frame, 3)?, 3);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.step_back_n(&mut frame, 3)?, 1);
+        assert_eq!(snapshot(&frame), initial);
+
+        Ok(())
+    }
+
+    #[test]
+    fn step_back_to_breakpoint() -> Result<(), FrameError> {
+        let mut frame = create_frame(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": {},
+            "stack": []
+        }));
+        let mut history = History::new();
+
+        frame
+            .debug
+            .set_breakpoint(Position::from_numeric(2, 0).unwrap());
+        for _ in 0..6 {
+            history.step(&mut frame)?;
+        }
+
+        assert_eq!(history.step_back_to_breakpoint(&mut frame)?, 4);
+        assert_eq!(frame.head.position, Position::from_numeric(2, 0).unwrap());
+
+        assert_eq!(history.step_back_to_breakpoint(&mut frame)?, 2);
+        assert_eq!(frame.head.position, Position::ORIGIN);
+        assert!(history.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn limit() -> Result<(), FrameError> {
+        let mut frame = Frame::from_example("loop").unwrap();
+        let mut history = History::with_limit(2);
+
+        for _ in 0..5 {
+            history.step(&mut frame)?;
+        }
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.step_back_n(&mut frame, 5)?, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn revert_breakpoint_actions() {
+        let mut frame = Frame::from_example("loop").unwrap();
+        let position = Position::from_numeric(1, 1).unwrap();
+
+        let revert = frame.act(FrameAction::ToggleBreakpoint(position)).unwrap();
+        assert!(frame.debug.has_breakpoint(position));
+
+        frame.revert(revert).unwrap();
+        assert!(!frame.debug.has_breakpoint(position));
+    }
+}
@@ -0,0 +1,622 @@
+use std::{
+    io::{BufRead, Write},
+    sync::{Arc, Mutex},
+};
+
+use act::{Revert, State};
+
+use crate::{
+    Errored, Frame, FrameError, FrameFile, FrameFileError, Fuel, HeadAction, History,
+    HostFunctions, Literal, Operand, Position, Random, device::Device, text::TextParseError,
+};
+
+/// Runs a [`Frame`], the simplest way to embed the interpreter
+///
+/// An `Interpreter` is obtained from an [`InterpreterBuilder`], which loads the frame, attaches
+/// input and output, and sets the limits of the execution. It then [runs](Interpreter::run) the
+/// frame until something worth stopping for happens, see [`Outcome`].
+///
+/// # Input and output
+/// Programs read and write through [host functions](crate::HostFunctions) :
+/// - `in` takes no argument, and pushes the next line of the input, or an errored operand once
+///   the input is exhausted
+/// - `out` takes one argument, and writes it to the output, followed by a new line
+///
+/// # Examples
+/// ```
+/// # use grai::{Break, Interpreter, Outcome};
+/// let mut interpreter = Interpreter::builder()
+///     .example("countdown")
+///     .max_steps(1000)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::Halted));
+/// assert_eq!(interpreter.frame().grid.get("AB".parse().unwrap()).as_str(), "0");
+/// ```
+///
+/// ```
+/// # use grai::{Interpreter, Outcome};
+/// // Prints `abc`
+/// let mut interpreter = Interpreter::builder()
+///     .text("A |abc|out|cal|")
+///     .output(std::io::stdout())
+///     .max_steps(3)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(interpreter.run().unwrap(), Outcome::StepLimit);
+/// ```
+#[derive(Debug)]
+pub struct Interpreter {
+    frame: Frame,
+    history: Option<History>,
+    max_steps: Option<u64>,
+    on_error: ErrorPolicy,
+
+    steps: u64,
+    errors: Vec<(Position, FrameError)>,
+}
+
+/// What to do when a step errors, see [`InterpreterBuilder::on_error()`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop there, returning the error
+    #[default]
+    Stop,
+
+    /// Step the head over the erroring cell and go on, the error is kept in
+    /// [`Interpreter::errors()`]
+    ///
+    /// At the edge of the grid, where the head can't step over the cell, the cell would error
+    /// again and again, running breaks off with [`Break::Halted`] instead
+    Skip,
+}
+
+/// Why running a [`Frame`] breaks off by itself, be it by an [`Interpreter`] or a
+/// [`Worker`](crate::worker::Worker)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Break {
+    /// The head is stuck on an empty cell at the edge of the grid, or on an erroring cell skipped
+    /// with [`ErrorPolicy::Skip`], nothing will ever happen
+    Halted,
+
+    /// The head landed on a breakpoint
+    Breakpoint,
+
+    /// A watchpoint was hit, see [`Frame::watch_hits()`]
+    Watchpoint,
+
+    /// The frame does not have enough [`Fuel`] for the next step, it can be run again after
+    /// refuelling
+    OutOfFuel,
+}
+
+/// Why [`Interpreter::run()`] returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Break(Break),
+
+    /// The maximum number of steps was reached
+    StepLimit,
+}
+
+/// A step of a frame, see [`step()`]
+pub(crate) struct Stepped {
+    /// Reverts the step, `None` if the frame ran out of fuel before it
+    pub revert: Option<Revert>,
+
+    /// Why running the frame breaks off after the step, if it does
+    pub stop: Option<Break>,
+}
+
+/// Step `frame` once, `on_error` is given the error of a failing step, and returns either the
+/// revert of what it did instead, or the error to stop on
+pub(crate) fn step(
+    frame: &mut Frame,
+    on_error: impl FnOnce(&mut Frame, FrameError) -> Result<Revert, FrameError>,
+) -> Result<Stepped, FrameError> {
+    let position = frame.head.position;
+    let mut stuck = frame.grid.word(position).is_none();
+
+    let revert = match frame.step() {
+        Ok(revert) => revert,
+        Err(FrameError::OutOfFuel { .. }) => {
+            return Ok(Stepped {
+                revert: None,
+                stop: Some(Break::OutOfFuel),
+            });
+        }
+        Err(err) => {
+            stuck = true;
+            on_error(frame, err)?
+        }
+    };
+
+    // An empty or skipped cell the head did not leave is executed the same way forever
+    let stop = if stuck && frame.head.position == position {
+        Some(Break::Halted)
+    } else if frame.debug.has_breakpoint(frame.head.position) {
+        Some(Break::Breakpoint)
+    } else if !frame.watch_hits().is_empty() {
+        Some(Break::Watchpoint)
+    } else {
+        None
+    };
+
+    Ok(Stepped {
+        revert: Some(revert),
+        stop,
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InterpreterError {
+    #[error("no frame to interpret")]
+    NoFrame,
+
+    #[error("no example named `{0}`")]
+    UnknownExample(String),
+
+    #[error(transparent)]
+    File(#[from] FrameFileError),
+
+    #[error(transparent)]
+    Text(#[from] TextParseError),
+}
+
+/// Where the frame of an [`InterpreterBuilder`] comes from
+#[derive(Debug)]
+enum Source {
+    Frame(Box<Frame>),
+    Json(String),
+    Example(String),
+    Text(String),
+}
+
+/// Registers the host functions of a [`Device`]
+type AttachDevice = Box<dyn FnOnce(&mut HostFunctions) + Send>;
+
+/// Configures an [`Interpreter`], see [`Interpreter::builder()`]
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    source: Option<Source>,
+    input: Option<Box<dyn BufRead + Send>>,
+    output: Option<Box<dyn Write + Send>>,
+    max_steps: Option<u64>,
+    fuel: Option<Fuel>,
+    seed: Option<u64>,
+    history: Option<usize>,
+    on_error: ErrorPolicy,
+
+    /// Attach each device to the frame
+    devices: Vec<AttachDevice>,
+}
+
+impl InterpreterBuilder {
+    /// Interpret `frame`
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.source = Some(Source::Frame(Box::new(frame)));
+        self
+    }
+
+    /// Interpret the frame of a [`FrameFile`], in JSON
+    pub fn json(mut self, json: &str) -> Self {
+        self.source = Some(Source::Json(json.to_string()));
+        self
+    }
+
+    /// Interpret a bundled example, see [`examples::names()`](crate::examples::names)
+    pub fn example(mut self, name: &str) -> Self {
+        self.source = Some(Source::Example(name.to_string()));
+        self
+    }
+
+    /// Interpret a frame in the [text format](crate::text)
+    pub fn text(mut self, source: &str) -> Self {
+        self.source = Some(Source::Text(source.to_string()));
+        self
+    }
+
+    /// Read the input of the program from `input`, see [input and
+    /// output](Interpreter#input-and-output)
+    pub fn input(mut self, input: impl BufRead + Send + 'static) -> Self {
+        self.input = Some(Box::new(input));
+        self
+    }
+
+    /// Write the output of the program to `output`, see [input and
+    /// output](Interpreter#input-and-output)
+    pub fn output(mut self, output: impl Write + Send + 'static) -> Self {
+        self.output = Some(Box::new(output));
+        self
+    }
+
+    /// Let the program drive `device`, see [`device`](crate::device)
+    pub fn device<D: Device>(mut self, device: &Arc<Mutex<D>>) -> Self {
+        let device = Arc::clone(device);
+        self.devices
+            .push(Box::new(move |functions| D::attach(&device, functions)));
+        self
+    }
+
+    /// Stop [running](Interpreter::run) after `steps` steps in total
+    pub fn max_steps(mut self, steps: u64) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Limit the work of the frame, see [`Fuel`]
+    pub fn fuel(mut self, fuel: Fuel) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Seed the generator of the numbers drawn by `rnd`, see [`Random`]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Keep the last `limit` steps, to [step back](Interpreter::step_back)
+    pub fn history(mut self, limit: usize) -> Self {
+        self.history = Some(limit);
+        self
+    }
+
+    pub fn on_error(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = policy;
+        self
+    }
+
+    /// Load the frame and obtain the `Interpreter`
+    ///
+    /// # Errors
+    /// Returns an error if no frame was given, or if it could not be loaded
+    pub fn build(self) -> Result<Interpreter, InterpreterError> {
+        let mut frame = match self.source.ok_or(InterpreterError::NoFrame)? {
+            Source::Frame(frame) => *frame,
+            Source::Json(json) => FrameFile::from_json(&json)?.frame,
+            Source::Example(name) => {
+                Frame::from_example(&name).ok_or(InterpreterError::UnknownExample(name))?
+            }
+            Source::Text(source) => Frame::from_text(&source)?,
+        };
+
+        if let Some(input) = self.input {
+            let input = Mutex::new(input);
+            frame
+                .functions
+                .register("in", 0, move |_| {
+                    let mut line = String::new();
+                    let read = input
+                        .lock()
+                        .expect("the input lock should not be poisoned")
+                        .read_line(&mut line)
+                        .map_err(|err| err.to_string())?;
+
+                    let operand: Operand = if read == 0 {
+                        Errored::new().into()
+                    } else {
+                        Literal::from_str(line.trim_end_matches(['\n', '\r']))
+                            .map_err(|err| err.to_string())?
+                            .into()
+                    };
+
+                    Ok(vec![operand])
+                })
+                .expect("`in` should be a valid function name");
+        }
+
+        if let Some(output) = self.output {
+            let output = Mutex::new(output);
+            frame
+                .functions
+                .register("out", 1, move |args| {
+                    let mut output = output
+                        .lock()
+                        .expect("the output lock should not be poisoned");
+                    writeln!(output, "{}", args[0])
+                        .and_then(|_| output.flush())
+                        .map_err(|err| err.to_string())?;
+
+                    Ok(Vec::new())
+                })
+                .expect("`out` should be a valid function name");
+        }
+
+        for attach in self.devices {
+            attach(&mut frame.functions);
+        }
+
+        if self.fuel.is_some() {
+            frame.fuel = self.fuel;
+        }
+
+        if let Some(seed) = self.seed {
+            frame.random = Random::new(seed);
+        }
+
+        Ok(Interpreter {
+            frame,
+            history: self.history.map(History::with_limit),
+            max_steps: self.max_steps,
+            on_error: self.on_error,
+            steps: 0,
+            errors: Vec::new(),
+        })
+    }
+}
+
+impl Interpreter {
+    pub fn builder() -> InterpreterBuilder {
+        InterpreterBuilder::default()
+    }
+
+    /// Step until something worth stopping for happens, see [`Outcome`]
+    ///
+    /// # Errors
+    /// Returns the error of the step that failed, if the [`ErrorPolicy`] is to stop
+    pub fn run(&mut self) -> Result<Outcome, FrameError> {
+        loop {
+            if let Some(outcome) = self.step()? {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Step once, returns why a [run](Interpreter::run) would stop here, if it would
+    ///
+    /// # Errors
+    /// Returns the error of the step, if the [`ErrorPolicy`] is to stop
+    pub fn step(&mut self) -> Result<Option<Outcome>, FrameError> {
+        if self.max_steps.is_some_and(|max| self.steps >= max) {
+            return Ok(Some(Outcome::StepLimit));
+        }
+
+        let stepped = step(&mut self.frame, |frame, err| match self.on_error {
+            ErrorPolicy::Stop => Err(err),
+            ErrorPolicy::Skip => {
+                // The failed step left the frame unchanged
+                self.errors.push((frame.head.position, err));
+                Ok(frame
+                    .act(HeadAction::Step)
+                    .expect("stepping the head should not fail"))
+            }
+        })?;
+
+        if let Some(revert) = stepped.revert {
+            self.steps += 1;
+            if let Some(history) = &mut self.history {
+                history.record(revert);
+            }
+        }
+
+        Ok(stepped.stop.map(Outcome::Break))
+    }
+
+    /// Revert the last step, returns `false` if there is nothing to step back, always the case
+    /// without [history](InterpreterBuilder::history)
+    ///
+    /// # Errors
+    /// Returns an error if the step could not be reverted
+    pub fn step_back(&mut self) -> Result<bool, FrameError> {
+        let Some(history) = &mut self.history else {
+            return Ok(false);
+        };
+
+        let stepped = history.step_back(&mut self.frame)?;
+        if stepped {
+            self.steps -= 1;
+        }
+
+        Ok(stepped)
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+
+    pub fn into_frame(self) -> Frame {
+        self.frame
+    }
+
+    /// Number of steps run
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// The errors skipped with [`ErrorPolicy::Skip`], along with the position of the head
+    pub fn errors(&self) -> &[(Position, FrameError)] {
+        &self.errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::FrameAction;
+
+    /// An output that can be read back once moved into the interpreter
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn sources() {
+        let countdown = || Frame::from_example("countdown").unwrap();
+        let json = FrameFile::new(countdown(), Default::default()).to_json();
+
+        for builder in [
+            Interpreter::builder().example("countdown"),
+            Interpreter::builder().json(&json),
+            Interpreter::builder().text(&countdown().to_text()),
+            Interpreter::builder().frame(countdown()),
+        ] {
+            let mut interpreter = builder.build().unwrap();
+            assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::Halted));
+            assert_eq!(
+                interpreter.frame().grid.get("AB".parse().unwrap()).as_str(),
+                "0"
+            );
+        }
+
+        assert!(matches!(
+            Interpreter::builder().build().unwrap_err(),
+            InterpreterError::NoFrame
+        ));
+        assert!(matches!(
+            Interpreter::builder().example("nope").build().unwrap_err(),
+            InterpreterError::UnknownExample(_)
+        ));
+        assert!(matches!(
+            Interpreter::builder().json("{").build().unwrap_err(),
+            InterpreterError::File(_)
+        ));
+        assert!(matches!(
+            Interpreter::builder().text("A |abcd|").build().unwrap_err(),
+            InterpreterError::Text(_)
+        ));
+    }
+
+    #[test]
+    fn input_output() {
+        let output = Output::default();
+        let mut interpreter = Interpreter::builder()
+            .text("A |in |cal|out|cal|@AA|jmp|")
+            .input(Cursor::new("abc\ndef\n"))
+            .output(output.clone())
+            .max_steps(18)
+            .build()
+            .unwrap();
+
+        assert_eq!(interpreter.run().unwrap(), Outcome::StepLimit);
+        assert_eq!(interpreter.steps(), 18);
+
+        // Once the input is exhausted, an errored operand is read
+        assert_eq!(output.text(), "abc\ndef\n###\n");
+    }
+
+    #[test]
+    fn limits() {
+        let mut interpreter = Interpreter::builder()
+            .example("countdown")
+            .max_steps(15)
+            .history(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(interpreter.run().unwrap(), Outcome::StepLimit);
+        assert_eq!(interpreter.step().unwrap(), Some(Outcome::StepLimit));
+        assert_eq!(interpreter.steps(), 15);
+
+        assert!(interpreter.step_back().unwrap());
+        assert_eq!(interpreter.steps(), 14);
+        assert_eq!(interpreter.frame().head.position, "EA".parse().unwrap());
+
+        let mut interpreter = Interpreter::builder()
+            .example("countdown")
+            .fuel(Fuel::new(5))
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::OutOfFuel));
+
+        interpreter
+            .frame_mut()
+            .fuel
+            .as_mut()
+            .unwrap()
+            .refuel(10_000);
+        assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::Halted));
+    }
+
+    #[test]
+    fn errors() {
+        let build = |policy| {
+            Interpreter::builder()
+                .text("A |jmp|abc|")
+                .on_error(policy)
+                .build()
+                .unwrap()
+        };
+
+        let mut interpreter = build(ErrorPolicy::Stop);
+        assert!(matches!(
+            interpreter.run().unwrap_err(),
+            FrameError::FetchOperand(_)
+        ));
+        assert_eq!(interpreter.steps(), 0);
+
+        let mut interpreter = build(ErrorPolicy::Skip);
+        assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::Halted));
+        assert_eq!(interpreter.errors().len(), 1);
+        assert_eq!(interpreter.errors()[0].0, "AA".parse().unwrap());
+        assert_eq!(interpreter.frame().stack.last().unwrap().to_string(), "abc");
+
+        // Skipping a cell at the edge of the grid leaves the head on it
+        let mut interpreter = Interpreter::builder()
+            .text("head AA left\nA |jmp|")
+            .on_error(ErrorPolicy::Skip)
+            .build()
+            .unwrap();
+        assert_eq!(interpreter.run().unwrap(), Outcome::Break(Break::Halted));
+        assert_eq!(interpreter.errors().len(), 1);
+        assert_eq!(interpreter.steps(), 1);
+    }
+
+    #[test]
+    fn skip_and_step_back() {
+        let mut interpreter = Interpreter::builder()
+            .text("A |xyz|@AB|set|")
+            .history(10)
+            .on_error(ErrorPolicy::Skip)
+            .build()
+            .unwrap();
+        let _ = interpreter
+            .frame_mut()
+            .act(FrameAction::Protect("AB".parse().unwrap()))
+            .unwrap();
+        let stack = |interpreter: &Interpreter| {
+            interpreter
+                .frame()
+                .stack
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        for _ in 0..3 {
+            assert_eq!(interpreter.step().unwrap(), None);
+        }
+        assert_eq!(interpreter.errors().len(), 1);
+
+        // The failed `set` is rolled back, only the head moved past it
+        assert_eq!(stack(&interpreter), ["xyz", "@AB"]);
+        assert_eq!(interpreter.frame().head.position, "DA".parse().unwrap());
+
+        assert!(interpreter.step_back().unwrap());
+        assert_eq!(stack(&interpreter), ["xyz", "@AB"]);
+        assert_eq!(interpreter.frame().head.position, "CA".parse().unwrap());
+    }
+}
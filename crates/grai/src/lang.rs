@@ -4,11 +4,21 @@ pub use opcode::*;
 mod operand;
 pub use operand::*;
 
+mod custom;
+pub use custom::*;
+
 use crate::Cell;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Word {
     Operand(Operand),
     Opcode(Opcode),
+
+    /// An opcode registered by the embedding application, see [`CustomOpcodes`]
+    ///
+    /// Only a [`Frame`](crate::Frame) knows its custom opcodes, [`Word::from_cell()`] never
+    /// decodes to this variant, see [`Frame::word()`](crate::Frame::word)
+    Custom(CustomOpcode),
 }
 
 impl Word {
@@ -20,4 +30,20 @@ impl Word {
             Self::Operand(Operand::from_cell(cell))
         }
     }
+
+    pub fn kind(&self) -> WordKind {
+        match self {
+            Self::Operand(operand) => WordKind::Operand(operand.kind()),
+            Self::Opcode(_) => WordKind::Opcode,
+            Self::Custom(_) => WordKind::Custom,
+        }
+    }
+}
+
+/// The kind of a [`Word`], without its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordKind {
+    Operand(OperandKind),
+    Opcode,
+    Custom,
 }
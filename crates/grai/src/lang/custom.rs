@@ -0,0 +1,313 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+
+use act::{Revert, State, TimelineRef};
+
+use crate::{Frame, FrameError, HeadAction, Opcode, Operand, Word};
+
+/// Handles a [`CustomOpcode`], see [`CustomOpcodes::register()`]
+pub type OpcodeHandler =
+    dyn Fn(&mut TimelineRef<Frame>) -> Result<HeadMove, FrameError> + Send + Sync;
+
+/// What the head does once the handler of a [`CustomOpcode`] returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadMove {
+    /// Step forward, to the next cell
+    Step,
+
+    /// Stay where the handler left it, as after a jump
+    Stay,
+}
+
+/// An opcode registered by the application embedding the interpreter, alongside the built-in
+/// [`Opcode`]s, see [`CustomOpcodes`]
+#[derive(Clone)]
+pub struct CustomOpcode {
+    name: String,
+    handler: Arc<OpcodeHandler>,
+}
+
+/// The [`CustomOpcode`]s a [`Frame`] executes, by name
+///
+/// Like built-in opcodes, names are three ASCII letters, matched case-insensitively. The
+/// [`Grid`](crate::Grid) only knows built-in opcodes and decodes a cell holding such a name to a
+/// literal, the frame executes it as the opcode registered under that name when it steps on it,
+/// see [`Frame::word()`]. Registering or unregistering an opcode thus applies to the cells already
+/// decoded.
+///
+/// # Examples
+/// ```
+/// # use grai::{Frame, HeadMove, Literal, StackAction};
+/// let mut frame = Frame::from_text("A |ans|").unwrap();
+///
+/// // Push the answer
+/// frame
+///     .opcodes
+///     .register("ans", |frame| {
+///         frame.act(StackAction::Push(Literal::from_str("42").unwrap().into()))?;
+///         Ok(HeadMove::Step)
+///     })
+///     .unwrap();
+///
+/// let _ = frame.step().unwrap();
+///
+/// assert_eq!(frame.stack.last().unwrap().to_string(), "42");
+/// assert_eq!(frame.head.position, "BA".parse().unwrap());
+/// ```
+#[derive(Clone, Default)]
+pub struct CustomOpcodes {
+    opcodes: HashMap<String, CustomOpcode>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RegisterOpcodeError {
+    #[error("an opcode name must be three ASCII letters, got `{0}`")]
+    InvalidName(String),
+
+    #[error("`{0}` is a built-in opcode")]
+    Builtin(Opcode),
+
+    #[error("`{0}` is already registered")]
+    AlreadyRegistered(String),
+}
+
+impl CustomOpcodes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new opcode named `name`, executed by `handler`
+    ///
+    /// The handler applies its changes through the given [`TimelineRef`], so that stepping over
+    /// the opcode can be reverted. It then tells with a [`HeadMove`] whether the head steps
+    /// forward, or stays where the handler moved it, as after `jmp`.
+    ///
+    /// # Errors
+    /// Returns an error if `name` is not three ASCII letters, or if it is already the name of an
+    /// opcode, built-in or registered
+    pub fn register(
+        &mut self,
+        name: &str,
+        handler: impl Fn(&mut TimelineRef<Frame>) -> Result<HeadMove, FrameError>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Result<CustomOpcode, RegisterOpcodeError> {
+        if name.len() != 3 || !name.chars().all(|char| char.is_ascii_alphabetic()) {
+            return Err(RegisterOpcodeError::InvalidName(name.to_string()));
+        }
+
+        if let Ok(opcode) = name.parse::<Opcode>() {
+            return Err(RegisterOpcodeError::Builtin(opcode));
+        }
+
+        let name = name.to_ascii_lowercase();
+        if self.opcodes.contains_key(&name) {
+            return Err(RegisterOpcodeError::AlreadyRegistered(name));
+        }
+
+        let opcode = CustomOpcode {
+            name: name.clone(),
+            handler: Arc::new(handler),
+        };
+        self.opcodes.insert(name, opcode.clone());
+
+        Ok(opcode)
+    }
+
+    /// Unregister the opcode named `name`, returns `false` if there was none
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.opcodes.remove(&name.to_ascii_lowercase()).is_some()
+    }
+
+    /// Get the opcode registered under `name`, if any
+    pub fn get(&self, name: &str) -> Option<CustomOpcode> {
+        if name.len() != 3 || self.opcodes.is_empty() {
+            return None;
+        }
+
+        self.opcodes.get(&name.to_ascii_lowercase()).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.opcodes.is_empty()
+    }
+
+    /// Get the opcode `word` names, if it is a literal holding the name of a registered opcode
+    pub fn named_by(&self, word: &Word) -> Option<CustomOpcode> {
+        match word {
+            Word::Operand(Operand::Literal(literal)) => self.get(literal.as_str()),
+            _ => None,
+        }
+    }
+}
+
+impl Debug for CustomOpcodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.opcodes.keys()).finish()
+    }
+}
+
+impl CustomOpcode {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn evaluate(&self, frame: &mut Frame) -> Result<Revert, <Frame as State>::Error> {
+        frame.apply_or_rollback(|frame| {
+            if (self.handler)(frame)? == HeadMove::Step {
+                frame.act(HeadAction::Step)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl PartialEq for CustomOpcode {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.handler, &other.handler)
+    }
+}
+
+impl Eq for CustomOpcode {}
+
+impl Debug for CustomOpcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CustomOpcode").field(&self.name).finish()
+    }
+}
+
+impl Display for CustomOpcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Cell, Direction, FetchOperandError, Literal, Position, StackAction, StackError,
+        pop_as_number, position,
+    };
+
+    #[test]
+    fn register() {
+        let handler = |_: &mut TimelineRef<Frame>| Ok(HeadMove::Step);
+        let mut opcodes = CustomOpcodes::new();
+
+        assert_eq!(
+            opcodes.register("ab", handler).unwrap_err(),
+            RegisterOpcodeError::InvalidName("ab".to_string())
+        );
+        assert_eq!(
+            opcodes.register("@AB", handler).unwrap_err(),
+            RegisterOpcodeError::InvalidName("@AB".to_string())
+        );
+        assert_eq!(
+            opcodes.register("ADD", handler).unwrap_err(),
+            RegisterOpcodeError::Builtin(Opcode::Add)
+        );
+
+        let opcode = opcodes.register("Nul", handler).unwrap();
+        assert_eq!(opcode.name(), "nul");
+        assert_eq!(
+            opcodes.register("nUL", handler).unwrap_err(),
+            RegisterOpcodeError::AlreadyRegistered("nul".to_string())
+        );
+
+        // The grid only knows built-in opcodes
+        let word = Word::from_cell(Cell::new_trim("NUL"));
+        assert!(matches!(word, Word::Operand(_)));
+        assert_eq!(opcodes.named_by(&word), Some(opcode));
+
+        assert!(opcodes.unregister("nul"));
+        assert!(!opcodes.unregister("nul"));
+        assert_eq!(opcodes.named_by(&word), None);
+    }
+
+    /// Double the number on top of the stack with `dbl`, jump back to the start of the row with
+    /// `ret`
+    fn opcodes() -> CustomOpcodes {
+        let mut opcodes = CustomOpcodes::new();
+
+        opcodes
+            .register("dbl", |frame| {
+                let number = pop_as_number(frame)?.unwrap_or_default();
+                let literal =
+                    Literal::try_from_number(number * 2).map_err(FetchOperandError::from)?;
+                frame.act(StackAction::Push(literal.into()))?;
+                Ok(HeadMove::Step)
+            })
+            .unwrap();
+
+        opcodes
+            .register("ret", |frame| {
+                let position = Position::from_numeric(0, frame.head.position.y()).unwrap();
+                frame.act(HeadAction::MoveTo(position))?;
+                Ok(HeadMove::Stay)
+            })
+            .unwrap();
+
+        opcodes
+    }
+
+    #[test]
+    fn evaluate() {
+        let mut frame = Frame::from_text("A |21 |dbl|ret|").unwrap();
+        frame.opcodes = opcodes();
+
+        let _ = frame.step().unwrap();
+        let doubled = frame.step().unwrap();
+        assert_eq!(frame.stack.last().unwrap().to_string(), "42");
+        assert_eq!(frame.head.position, position("CA"));
+
+        let returned = frame.step().unwrap();
+        assert_eq!(frame.head.position, position("AA"));
+        assert_eq!(frame.head.direction, Direction::Right);
+
+        // Stepping over a custom opcode is reverted like any other step
+        frame.revert(returned).unwrap();
+        frame.revert(doubled).unwrap();
+        assert_eq!(frame.stack.last().unwrap().to_string(), "21");
+        assert_eq!(frame.head.position, position("BA"));
+
+        // Errors of the handler are the step's
+        let mut frame = Frame::from_text("A |dbl|").unwrap();
+        frame.opcodes = opcodes();
+        assert_eq!(
+            frame.step().unwrap_err(),
+            FrameError::FetchOperand(FetchOperandError::StackError(StackError::EmptyStack))
+        );
+
+        // A handler can keep the head on its own cell
+        let mut frame = Frame::from_text("A |ret|").unwrap();
+        frame.opcodes = opcodes();
+        let _ = frame.step().unwrap();
+        assert_eq!(frame.head.position, position("AA"));
+    }
+
+    #[test]
+    fn decoded_cells() {
+        let mut frame = Frame::from_text("A |21 |dbl|").unwrap();
+
+        // Once decoded by the grid, the cell still runs the opcodes registered afterwards
+        assert!(frame.grid.word(position("BA")).is_some());
+        frame.opcodes = opcodes();
+        assert!(matches!(frame.word(position("BA")), Some(Word::Custom(_))));
+
+        let _ = frame.step().unwrap();
+        let _ = frame.step().unwrap();
+        assert_eq!(frame.stack.last().unwrap().to_string(), "42");
+
+        // And stops running them once unregistered
+        assert!(frame.opcodes.unregister("dbl"));
+        frame.head.position = position("BA");
+        let _ = frame.step().unwrap();
+        assert_eq!(frame.stack.last().unwrap().to_string(), "dbl");
+    }
+}
@@ -3,15 +3,29 @@ use std::str::FromStr;
 use act::{Revert, State, TimelineRef};
 
 use crate::{
-    Address, Cell, Direction, Errored, ErroredEncountered, Frame, FrameError, GridAction,
-    HeadAction, Literal, LiteralFormatError, NotAnAddress, Operand, ParseLiteralAsBoolError,
-    ParseLiteralAsNumberError, PointerLoopError, ResolveToAddressError, ResolveToLiteralError,
-    StackAction, StackError,
+    Address, CallError, Cell, Direction, Errored, ErroredEncountered, Frame, FrameError,
+    GridAction, HeadAction, Literal, LiteralFormatError, NotAnAddress, Operand,
+    ParseLiteralAsBoolError, ParseLiteralAsNumberError, PointerLoopError, ProtectedError,
+    RandomAction, ResolveToAddressError, ResolveToLiteralError, StackAction, StackError,
 };
 
 // TODO: Split to have a multiples enums for each types of operands
-#[derive(Debug, strum_macros::EnumString)]
-#[strum(ascii_case_insensitive)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+    strum_macros::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+#[serde(try_from = "String")]
+#[serde(into = "String")]
 pub enum Opcode {
     // Program
     Nop,
@@ -44,6 +58,26 @@ pub enum Opcode {
     Lst,
     Grq,
     Lsq,
+
+    // Host function call, see `HostFunctions`
+    Cal,
+
+    // Random number, see `Random`
+    Rnd,
+}
+
+impl TryFrom<String> for Opcode {
+    type Error = strum::ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Opcode> for String {
+    fn from(value: Opcode) -> Self {
+        value.to_string()
+    }
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
@@ -74,6 +108,12 @@ pub enum FetchOperandError {
 pub enum EvaluationError {
     #[error(transparent)]
     FetchOperand(#[from] FetchOperandError),
+
+    #[error(transparent)]
+    Call(#[from] CallError),
+
+    #[error(transparent)]
+    Protected(#[from] ProtectedError),
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
@@ -82,7 +122,8 @@ pub struct NotAnOpcode {
     got: String,
 }
 
-fn pop_operand(frame: &mut TimelineRef<Frame>) -> Result<Operand, FetchOperandError> {
+/// Pop the operand on top of the stack
+pub fn pop_operand(frame: &mut TimelineRef<Frame>) -> Result<Operand, FetchOperandError> {
     let popped = frame.stack.last().cloned();
 
     frame.act(StackAction::Pop).map_err(|err| match err {
@@ -93,7 +134,8 @@ fn pop_operand(frame: &mut TimelineRef<Frame>) -> Result<Operand, FetchOperandEr
     Ok(popped.expect("stack.pop() must only return None when StackAction::Pop returned an Err"))
 }
 
-fn pop_to_address(frame: &mut TimelineRef<Frame>) -> Result<Address, FetchOperandError> {
+/// Pop the operand on top of the stack, and resolve it to an [`Address`]
+pub fn pop_to_address(frame: &mut TimelineRef<Frame>) -> Result<Address, FetchOperandError> {
     pop_operand(frame).and_then(|operand| {
         Ok(operand
             .resolve_to_address(&frame.grid)
@@ -109,7 +151,11 @@ fn pop_to_address(frame: &mut TimelineRef<Frame>) -> Result<Address, FetchOperan
     })
 }
 
-fn pop_to_literal(frame: &mut TimelineRef<Frame>) -> Result<Option<Literal>, FetchOperandError> {
+/// Pop the operand on top of the stack, and resolve it to a [`Literal`], `None` if it resolves
+/// to an [`Errored`] operand
+pub fn pop_to_literal(
+    frame: &mut TimelineRef<Frame>,
+) -> Result<Option<Literal>, FetchOperandError> {
     pop_operand(frame).and_then(|operand| match operand.resolve_to_literal(&frame.grid) {
         Ok(literal) => Ok(Some(literal)),
         Err(ResolveToLiteralError::ErroredEncountered(_)) => Ok(None),
@@ -117,18 +163,21 @@ fn pop_to_literal(frame: &mut TimelineRef<Frame>) -> Result<Option<Literal>, Fet
     })
 }
 
-fn pop_as_cell(frame: &mut TimelineRef<Frame>) -> Result<Cell, FetchOperandError> {
+/// Pop the operand on top of the stack, as the [`Cell`] it is written as
+pub fn pop_as_cell(frame: &mut TimelineRef<Frame>) -> Result<Cell, FetchOperandError> {
     pop_operand(frame).map(|operand| operand.to_cell())
 }
 
-fn pop_as_number(frame: &mut TimelineRef<Frame>) -> Result<Option<u32>, FetchOperandError> {
+/// Same as [`pop_to_literal()`], read as a number
+pub fn pop_as_number(frame: &mut TimelineRef<Frame>) -> Result<Option<u32>, FetchOperandError> {
     match pop_to_literal(frame)? {
         Some(literal) => Ok(Some(literal.try_as_number()?)),
         None => Ok(None),
     }
 }
 
-fn pop_as_bool(frame: &mut TimelineRef<Frame>) -> Result<Option<bool>, FetchOperandError> {
+/// Same as [`pop_to_literal()`], read as a boolean
+pub fn pop_as_bool(frame: &mut TimelineRef<Frame>) -> Result<Option<bool>, FetchOperandError> {
     match pop_to_literal(frame)? {
         Some(literal) => Ok(Some(literal.try_as_bool()?)),
         None => Ok(None),
@@ -143,10 +192,11 @@ impl Opcode {
     }
 
     pub fn evaluate(self, frame: &mut Frame) -> Result<Revert, <Frame as State>::Error> {
-        use Opcode::*;
-        let mut frame = TimelineRef::new(frame);
+        frame.apply_or_rollback(|frame| self.apply(frame))
+    }
 
-        dbg!(&self);
+    fn apply(self, frame: &mut TimelineRef<Frame>) -> Result<(), FrameError> {
+        use Opcode::*;
 
         match self {
             Nop => {}
@@ -154,7 +204,7 @@ impl Opcode {
             Gup | Gri | Gdo | Gle => {
                 let direction = match self {
                     Gup => Direction::Up,
-                    Gri => Direction::Left,
+                    Gri => Direction::Right,
                     Gdo => Direction::Down,
                     Gle => Direction::Left,
                     _ => unreachable!(),
@@ -164,15 +214,19 @@ impl Opcode {
             }
 
             Set => {
-                let at = pop_to_address(&mut frame)?;
-                let lit = pop_as_cell(&mut frame)?;
+                let at = pop_to_address(frame)?;
+                let lit = pop_as_cell(frame)?;
 
+                frame
+                    .protections
+                    .check(*at.position())
+                    .map_err(EvaluationError::from)?;
                 frame.act(GridAction::Set(*at.position(), lit.clone()))?;
             }
 
             Add | Sub | Mul | Div => {
-                let rhs_opt = pop_as_number(&mut frame)?;
-                let lhs_opt = pop_as_number(&mut frame)?;
+                let rhs_opt = pop_as_number(frame)?;
+                let lhs_opt = pop_as_number(frame)?;
 
                 let operand = match (rhs_opt, lhs_opt) {
                     (Some(rhs), Some(lhs)) => {
@@ -199,8 +253,8 @@ impl Opcode {
             }
 
             Equ | Neq => {
-                let rhs_opt = pop_to_literal(&mut frame)?;
-                let lhs_opt = pop_to_literal(&mut frame)?;
+                let rhs_opt = pop_to_literal(frame)?;
+                let lhs_opt = pop_to_literal(frame)?;
 
                 let operand = match (rhs_opt, lhs_opt) {
                     (Some(rhs), Some(lhs)) => {
@@ -220,8 +274,8 @@ impl Opcode {
             }
 
             Grt | Lst | Grq | Lsq => {
-                let rhs_opt = pop_as_number(&mut frame)?;
-                let lhs_opt = pop_as_number(&mut frame)?;
+                let rhs_opt = pop_as_number(frame)?;
+                let lhs_opt = pop_as_number(frame)?;
 
                 let operand = match (rhs_opt, lhs_opt) {
                     (Some(rhs), Some(lhs)) => {
@@ -241,17 +295,70 @@ impl Opcode {
                 frame.act(StackAction::Push(operand))?;
             }
 
+            Rnd => {
+                let max_opt = pop_as_number(frame)?;
+                let min_opt = pop_as_number(frame)?;
+
+                let drawn = match (min_opt, max_opt) {
+                    (Some(min), Some(max)) => frame.random.peek_range(min, max),
+                    (None, _) | (_, None) => None,
+                };
+
+                let operand = match drawn {
+                    Some((value, state)) => {
+                        frame.act(RandomAction::Seed(state))?;
+                        Literal::try_from_number(value)
+                            .map(Operand::from)
+                            .unwrap_or_else(|_| Errored::new().into())
+                    }
+                    None => Errored::new().into(),
+                };
+
+                frame.act(StackAction::Push(operand))?;
+            }
+
             Jmp => {
-                let address = pop_to_address(&mut frame)?;
+                let address = pop_to_address(frame)?;
                 frame.act(HeadAction::MoveTo(*address.position()))?;
             }
 
+            Cal => {
+                let name = pop_to_literal(frame)?
+                    .ok_or(CallError::ErroredName)
+                    .map_err(EvaluationError::from)?;
+                let function = frame.functions.get(&name).ok_or_else(|| {
+                    EvaluationError::from(CallError::UnknownFunction(name.clone()))
+                })?;
+
+                let mut args = Vec::with_capacity(function.arity);
+                for _ in 0..function.arity {
+                    args.push(match pop_to_literal(frame)? {
+                        Some(literal) => literal.into(),
+                        None => Errored::new().into(),
+                    });
+                }
+                args.reverse();
+
+                let results = (function.function)(&args).map_err(|message| {
+                    EvaluationError::from(CallError::Failed {
+                        function: name,
+                        message,
+                    })
+                })?;
+
+                for operand in results {
+                    frame.act(StackAction::Push(operand))?;
+                }
+            }
+
             Jif => {
-                let address = pop_to_address(&mut frame)?;
-                let condition_opt = pop_as_bool(&mut frame)?;
+                let address = pop_to_address(frame)?;
+                let condition_opt = pop_as_bool(frame)?;
 
                 if condition_opt.is_some_and(|condition| condition) {
                     frame.act(HeadAction::MoveTo(*address.position()))?;
+                } else {
+                    frame.act(HeadAction::Step)?;
                 }
             }
         };
@@ -260,6 +367,48 @@ impl Opcode {
             frame.act(HeadAction::Step)?;
         }
 
-        Ok(frame.into_revert())
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, Frame, position};
+
+    fn run(frame: &mut Frame, steps: usize) {
+        for _ in 0..steps {
+            let _ = frame.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn directions() {
+        for (opcode, direction) in [
+            ("gup", Direction::Up),
+            ("gri", Direction::Right),
+            ("gdo", Direction::Down),
+            ("gle", Direction::Left),
+        ] {
+            let mut frame = Frame::from_text(&format!("A |{opcode}|")).unwrap();
+            frame.head.direction = match direction {
+                Direction::Right => Direction::Left,
+                _ => Direction::Right,
+            };
+
+            run(&mut frame, 1);
+            assert_eq!(frame.head.direction, direction, "after `{opcode}`");
+        }
+    }
+
+    #[test]
+    fn jif() {
+        // A false condition steps past `jif`, instead of leaving the head on it
+        let mut frame = Frame::from_text("A |0  |@AB|jif|1  |").unwrap();
+        run(&mut frame, 3);
+        assert_eq!(frame.head.position, position("DA"));
+
+        let mut frame = Frame::from_text("A |1  |@AB|jif|1  |").unwrap();
+        run(&mut frame, 3);
+        assert_eq!(frame.head.position, position("AB"));
     }
 }
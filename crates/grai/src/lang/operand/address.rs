@@ -93,12 +93,12 @@ impl Address {
 
     /// Fetch the designated [`Operand`] in a [`Grid`]
     pub fn fetch_operand(&self, grid: &Grid) -> Operand {
-        Operand::from_cell(grid.get(*self.position()))
+        Operand::from_cell(grid.read(*self.position()))
     }
 
     /// Fetch the designated [`Literal`] in a [`Grid`]
     pub fn fetch_literal(&self, grid: &Grid) -> Literal {
-        Literal::from_cell(grid.get(*self.position()))
+        Literal::from_cell(grid.read(*self.position()))
     }
 }
 
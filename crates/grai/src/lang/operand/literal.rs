@@ -26,7 +26,7 @@ pub struct ParseLiteralAsNumberError {
 ///
 /// Differents operations can interpret a literal in differents ways :
 /// - As bool (see [`from_bool()`], [`as_bool_with_defaults()`] or [`try_as_bool()`])
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Literal(Cell);
 
 impl Literal {
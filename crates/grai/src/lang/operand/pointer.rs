@@ -16,7 +16,7 @@ pub enum PointerParseError {
     Position(#[from] PositionError),
 }
 
-#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
 #[error("could not resolve pointer chain, loop at `{looping_position}`")]
 pub struct PointerLoopError {
     last_pointer: Pointer,
@@ -103,7 +103,7 @@ impl Pointer {
             let next_position = current_pointer.position();
             visited_cells.push(*next_position);
 
-            let next_cell = grid.get(*next_position);
+            let next_cell = grid.read(*next_position);
             if let Ok(next_pointer) = Pointer::from_ref_cell(&next_cell) {
                 if visited_cells.contains(next_pointer.position()) {
                     // pointer chain loop
@@ -119,7 +119,11 @@ impl Pointer {
             }
         }
 
-        get(*self, grid, &mut Vec::new())
+        let mut visited_cells = Vec::new();
+        let operand = get(*self, grid, &mut visited_cells);
+        grid.followed(visited_cells.len());
+
+        operand
     }
 
     pub fn resolve_to_literal(&self, grid: &Grid) -> Result<Literal, ResolveToLiteralError> {
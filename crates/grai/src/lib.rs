@@ -5,3 +5,29 @@ mod frame;
 pub use frame::*;
 
 pub mod granary;
+
+pub mod text;
+
+pub mod archive;
+
+pub mod lint;
+
+pub mod flow;
+
+pub mod asm;
+
+pub mod disasm;
+
+pub mod worker;
+
+pub mod profile;
+
+pub mod device;
+
+mod analysis;
+
+mod history;
+pub use history::*;
+
+mod interpreter;
+pub use interpreter::*;
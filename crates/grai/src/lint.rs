@@ -0,0 +1,445 @@
+//! Static analysis of a [`Frame`], finding mistakes without running it
+//!
+//! [`lint()`] walks the grid from the head's starting position, following every path the head
+//! could take, while keeping track of what is known of the stack. Values computed at runtime
+//! (the result of `add`, `equ`…) are unknown, so a `jif` on such a value explores both
+//! branches.
+//!
+//! Cells written by a `set` are considered unknown from then on. When the head could execute
+//! such a cell, or jump to an unknown position, the analysis is incomplete and unreachable cells
+//! are not reported, to avoid false positives.
+//!
+//! # Examples
+//! ```
+//! # use grai::{Frame, lint::{self, LintKind, Severity}};
+//! let frame: Frame = serde_json::from_value(serde_json::json!({
+//!     "head": { "position": "AA", "direction": "Right" },
+//!     "grid": { "AA": "abc", "BA": "jmp" },
+//!     "stack": []
+//! })).unwrap();
+//!
+//! let lints = lint::lint(&frame);
+//! assert_eq!(lints.len(), 1);
+//! assert_eq!(lints[0].severity, Severity::Error);
+//! assert!(matches!(lints[0].kind, LintKind::NotAnAddress { .. }));
+//! ```
+
+use std::collections::HashSet;
+
+use strum::IntoEnumIterator;
+
+use crate::{Frame, Literal, Opcode, Operand, Pointer, PointerLoopError, Position, Word, analysis};
+
+/// How bad a [`Lint`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, strum_macros::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    /// The analysis could not go further, nothing is wrong per se
+    Info,
+
+    /// Probably a mistake
+    Warning,
+
+    /// The program errors if the head reaches this cell
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LintKind {
+    #[error("cell is never reached by the head, nor referenced")]
+    Unreachable,
+
+    #[error("`{got}` is read as a literal, did you mean `{opcode}` ?")]
+    ProbableTypo { got: String, opcode: Opcode },
+
+    #[error(transparent)]
+    PointerLoop(#[from] PointerLoopError),
+
+    #[error("`{opcode}` expects an address, but pops `{got}`")]
+    NotAnAddress { opcode: Opcode, got: Operand },
+
+    #[error("`{opcode}` jumps to the empty cell `{target}`")]
+    JumpIntoEmpty { opcode: Opcode, target: Position },
+
+    #[error("`{opcode}` pops an empty stack")]
+    StackUnderflow { opcode: Opcode },
+
+    #[error("target of `{opcode}` is unknown, analysis is incomplete")]
+    UnknownTarget { opcode: Opcode },
+
+    #[error("cell may be written by a `set` before being executed, analysis is incomplete")]
+    ModifiedCode,
+}
+
+impl LintKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::PointerLoop(_) | Self::NotAnAddress { .. } | Self::StackUnderflow { .. } => {
+                Severity::Error
+            }
+            Self::Unreachable | Self::ProbableTypo { .. } | Self::JumpIntoEmpty { .. } => {
+                Severity::Warning
+            }
+            Self::UnknownTarget { .. } | Self::ModifiedCode => Severity::Info,
+        }
+    }
+}
+
+/// A problem found at a given [`Position`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{severity} at `{position}` : {kind}")]
+pub struct Lint {
+    pub position: Position,
+    pub severity: Severity,
+    pub kind: LintKind,
+}
+
+impl Lint {
+    pub fn new(position: Position, kind: LintKind) -> Self {
+        Self {
+            position,
+            severity: kind.severity(),
+            kind,
+        }
+    }
+}
+
+/// Analyze `frame`, returning every [`Lint`] found, ordered by row, then column
+pub fn lint(frame: &Frame) -> Vec<Lint> {
+    let mut lints = Vec::new();
+
+    // Pointer loops are found anywhere in the grid, reached or not
+    for (position, cell) in frame.grid.cells() {
+        if let Ok(pointer) = Pointer::from_ref_cell(cell)
+            && let Err(err) = pointer.resolve_to_operand(&frame.grid)
+        {
+            lints.push(Lint::new(position, err.into()));
+        }
+    }
+
+    let analysis = analysis::analyze(frame);
+    lints.extend(analysis.lints);
+
+    for position in &analysis.visited {
+        if let Word::Operand(Operand::Literal(literal)) = Word::from_cell(frame.grid.get(*position))
+            && !analysis.written.contains(position)
+            && let Some(opcode) = probable_typo(&literal)
+        {
+            let got = literal.to_string();
+            lints.push(Lint::new(*position, LintKind::ProbableTypo { got, opcode }));
+        }
+    }
+
+    if analysis.complete {
+        let referenced = referenced_positions(frame);
+
+        for (position, _) in frame.grid.cells() {
+            if !analysis.visited.contains(&position)
+                && !referenced.contains(&position)
+                && !analysis.written.contains(&position)
+            {
+                lints.push(Lint::new(position, LintKind::Unreachable));
+            }
+        }
+    }
+
+    lints.sort_by_key(|lint| {
+        let (x, y) = lint.position.as_numeric();
+        (y, x, lint.severity)
+    });
+    lints.dedup();
+
+    lints
+}
+
+/// Every position that appears in an address or a pointer, in the grid or in the stack
+fn referenced_positions(frame: &Frame) -> HashSet<Position> {
+    let grid_operands = frame
+        .grid
+        .cells()
+        .map(|(_, cell)| Operand::from_cell(cell.clone()));
+
+    grid_operands
+        .chain(frame.stack.iter().cloned())
+        .filter_map(|operand| match operand {
+            Operand::Address(address) => Some(*address.position()),
+            Operand::Pointer(pointer) => Some(*pointer.position()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return the opcode `literal` is probably a typo of, if any
+///
+/// Only literals made of letters, one edit (insertion, deletion, substitution or transposition)
+/// away from an opcode, are considered typos
+fn probable_typo(literal: &Literal) -> Option<Opcode> {
+    let got = literal.as_str().to_ascii_lowercase();
+    if got.is_empty() || !got.chars().all(|char| char.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Opcode::iter().find(|opcode| edit_distance(&got, &opcode.to_string()) == 1)
+}
+
+/// Optimal string alignment distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn lint_json(json: serde_json::Value) -> Vec<Lint> {
+        lint(&serde_json::from_value(json).expect("must be a valid frame"))
+    }
+
+    fn kinds(lints: &[Lint]) -> Vec<(String, LintKind)> {
+        lints
+            .iter()
+            .map(|lint| (lint.position.as_textual_string(), lint.kind.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn examples_are_clean() {
+        for name in crate::examples::names() {
+            let frame = Frame::from_example(name).unwrap();
+            let errors: Vec<_> = lint(&frame)
+                .into_iter()
+                .filter(|lint| lint.severity > Severity::Info)
+                .collect();
+
+            assert!(errors.is_empty(), "example `{name}` : {errors:?}");
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "@AC", "BA": "jmp", "CA": "abc", "AB": "def", "AC": "gdo" },
+            "stack": []
+        }));
+
+        assert_eq!(
+            kinds(&lints),
+            [
+                (String::from("CA"), LintKind::Unreachable),
+                (String::from("AB"), LintKind::Unreachable),
+            ]
+        );
+    }
+
+    #[test]
+    fn probable_typo() {
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "1", "BA": "2", "CA": "sbu", "DA": "ad", "EA": "ad1", "FA": "abc" },
+            "stack": []
+        }));
+
+        assert_eq!(
+            kinds(&lints),
+            [
+                (
+                    String::from("CA"),
+                    LintKind::ProbableTypo {
+                        got: String::from("sbu"),
+                        opcode: Opcode::Sub
+                    }
+                ),
+                (
+                    String::from("DA"),
+                    LintKind::ProbableTypo {
+                        got: String::from("ad"),
+                        opcode: Opcode::Add
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn pointer_loop() {
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Down" },
+            "grid": { "AA": "&BA", "BA": "&CA", "CA": "&BA" },
+            "stack": []
+        }));
+
+        assert!(
+            lints
+                .iter()
+                .all(|lint| matches!(lint.kind, LintKind::PointerLoop(_)))
+        );
+        assert_eq!(
+            lints
+                .iter()
+                .map(|lint| lint.position.as_textual_string())
+                .collect::<Vec<_>>(),
+            ["AA", "BA", "CA"]
+        );
+    }
+
+    #[test]
+    fn jumps() {
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": {
+                "AA": "@AC", "BA": "jmp",
+                "AC": "abc", "BC": "jmp"
+            },
+            "stack": []
+        }));
+
+        assert_eq!(
+            kinds(&lints),
+            [(
+                String::from("BC"),
+                LintKind::NotAnAddress {
+                    opcode: Opcode::Jmp,
+                    got: Operand::from_str("abc").unwrap()
+                }
+            )]
+        );
+
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "@ZZ", "BA": "jmp" },
+            "stack": []
+        }));
+
+        assert_eq!(
+            kinds(&lints),
+            [(
+                String::from("BA"),
+                LintKind::JumpIntoEmpty {
+                    opcode: Opcode::Jmp,
+                    target: "ZZ".parse().unwrap()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn conditional_jumps() {
+        // The condition is unknown, both branches are explored
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": {
+                "AA": "1", "BA": "2", "CA": "equ", "DA": "@AC", "EA": "jif", "FA": "add",
+                "AC": "sub"
+            },
+            "stack": []
+        }));
+
+        assert_eq!(
+            kinds(&lints),
+            [
+                (
+                    String::from("FA"),
+                    LintKind::StackUnderflow {
+                        opcode: Opcode::Add
+                    }
+                ),
+                (
+                    String::from("AC"),
+                    LintKind::StackUnderflow {
+                        opcode: Opcode::Sub
+                    }
+                ),
+            ]
+        );
+
+        // The condition is known, the branch not taken is unreachable
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "1", "BA": "@AC", "CA": "jif", "DA": "add", "AC": "gdo" },
+            "stack": []
+        }));
+
+        assert_eq!(kinds(&lints), [(String::from("DA"), LintKind::Unreachable)]);
+    }
+
+    #[test]
+    fn stack_underflow() {
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "1", "BA": "add" },
+            "stack": []
+        }));
+
+        assert_eq!(
+            kinds(&lints),
+            [(
+                String::from("BA"),
+                LintKind::StackUnderflow {
+                    opcode: Opcode::Add
+                }
+            )]
+        );
+
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "1", "BA": "add" },
+            "stack": ["2"]
+        }));
+
+        assert!(lints.is_empty());
+    }
+
+    #[test]
+    fn modified_code() {
+        let lints = lint_json(json!({
+            "head": { "position": "AA", "direction": "Right" },
+            "grid": { "AA": "1", "BA": "@DA", "CA": "set", "DA": "nop", "AB": "abc" },
+            "stack": []
+        }));
+
+        // `AB` is not reported as unreachable, as the analysis is incomplete
+        assert_eq!(
+            kinds(&lints),
+            [(String::from("DA"), LintKind::ModifiedCode)]
+        );
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("add", "add"), 0);
+        assert_eq!(super::edit_distance("sbu", "sub"), 1);
+        assert_eq!(super::edit_distance("ad", "add"), 1);
+        assert_eq!(super::edit_distance("abc", "add"), 2);
+        assert_eq!(super::edit_distance("", "jmp"), 3);
+    }
+}
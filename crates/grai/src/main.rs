@@ -1,23 +1,17 @@
-use act::Timeline;
-use grai::{Frame, FrameAction, FrameGuard, PositionError};
+use grai::{Interpreter, InterpreterError};
 
-fn dbg_frame(frame: &FrameGuard) {
-    frame.read(|frame| {
-        println!("{}", serde_json::to_string_pretty(&frame).unwrap());
-    });
-}
-
-fn main() -> Result<(), PositionError> {
-    let frame = FrameGuard::new(Frame::from_example("inst").unwrap());
-
-    let mut timeline = Timeline::new(frame.clone());
-
-    dbg_frame(&frame);
+fn main() -> Result<(), InterpreterError> {
+    let mut interpreter = Interpreter::builder()
+        .example("inst")
+        .max_steps(5)
+        .build()?;
 
-    for _ in 0..5 {
-        timeline.act(FrameAction::Step).unwrap();
-        dbg_frame(&frame);
-    }
+    let outcome = interpreter.run();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(interpreter.frame()).unwrap()
+    );
+    println!("{outcome:?} after {} steps", interpreter.steps());
 
     Ok(())
 }
@@ -0,0 +1,366 @@
+//! Count what a [`Frame`](crate::Frame) does while it runs, to find hot loops
+//!
+//! Profiling is opt-in : once [`Frame::profile`](crate::Frame::profile) is set, every successful
+//! step is counted in the [`Profile`], how many times each cell was visited by the head, how many
+//! times each [`Opcode`] was executed, the highest the stack got, and how many pointers each
+//! pointer resolution followed. Reverting a step does not uncount it.
+//!
+//! A [`Report`] sums it up, along with the [`Coverage`] of the grid, and is exported either as
+//! JSON or as a human-readable table.
+//!
+//! # Examples
+//! ```
+//! # use grai::{Frame, profile::Profile};
+//! let mut frame = Frame::from_example("countdown").unwrap();
+//! frame.profile = Some(Profile::new());
+//!
+//! for _ in 0..100 {
+//!     let _ = frame.step().unwrap();
+//! }
+//!
+//! let report = frame.profile.as_ref().unwrap().report(&frame.grid);
+//! assert_eq!(report.cells[0].hits, 10);
+//! assert_eq!(report.cells[0].position, "AA".parse().unwrap());
+//!
+//! // The number counted down is data, it is never executed
+//! assert_eq!(report.coverage.never_executed, ["AB".parse().unwrap()]);
+//!
+//! let json = serde_json::to_string(&report).unwrap();
+//! let table = report.to_string();
+//! ```
+//!
+//! ```text
+//! steps                 100
+//! stack high-water mark 2
+//! coverage              10/11 cells executed
+//! never executed        AB
+//!
+//! cell    hits  content
+//! AA        10  @AB
+//! …
+//!
+//! opcode  executions
+//! sub             10
+//! …
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use crate::{Cell, Grid, Opcode, Position};
+
+/// What a [`Frame`](crate::Frame) did while profiled, see the [module documentation](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    steps: u64,
+    visits: HashMap<Position, u64>,
+    executions: HashMap<Opcode, u64>,
+    stack_high_water: usize,
+
+    /// Number of pointer resolutions, by number of pointers followed
+    pointer_depths: BTreeMap<usize, u64>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of steps counted
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Number of steps the head started on `position`
+    pub fn visits(&self, position: Position) -> u64 {
+        self.visits.get(&position).copied().unwrap_or(0)
+    }
+
+    /// Number of times `opcode` was executed
+    pub fn executions(&self, opcode: Opcode) -> u64 {
+        self.executions.get(&opcode).copied().unwrap_or(0)
+    }
+
+    /// The size of the stack at its highest
+    pub fn stack_high_water(&self) -> usize {
+        self.stack_high_water
+    }
+
+    /// The most pointers followed by a single pointer resolution
+    pub fn max_pointer_depth(&self) -> usize {
+        self.pointer_depths.keys().last().copied().unwrap_or(0)
+    }
+
+    /// Count a step that started on `position`, executing `opcode` if any
+    pub(crate) fn record(
+        &mut self,
+        position: Position,
+        opcode: Option<Opcode>,
+        depth: usize,
+        chains: &[usize],
+    ) {
+        self.steps += 1;
+        *self.visits.entry(position).or_default() += 1;
+
+        if let Some(opcode) = opcode {
+            *self.executions.entry(opcode).or_default() += 1;
+        }
+
+        self.stack_high_water = self.stack_high_water.max(depth);
+
+        for &pointers in chains {
+            *self.pointer_depths.entry(pointers).or_default() += 1;
+        }
+    }
+
+    /// Which non-empty cells of `grid` were executed
+    pub fn coverage(&self, grid: &Grid) -> Coverage {
+        let mut coverage = Coverage::default();
+
+        for (position, _) in grid.cells() {
+            coverage.cells += 1;
+
+            if self.visits.contains_key(&position) {
+                coverage.executed += 1;
+            } else {
+                coverage.never_executed.push(position);
+            }
+        }
+
+        coverage
+    }
+
+    /// Sum up the profile, `grid` being the profiled frame's grid
+    pub fn report(&self, grid: &Grid) -> Report {
+        let mut cells: Vec<CellHits> = self
+            .visits
+            .iter()
+            .map(|(&position, &hits)| CellHits {
+                position,
+                content: grid.get(position),
+                hits,
+            })
+            .collect();
+        cells.sort_by_key(|cell| {
+            let (x, y) = cell.position.as_numeric();
+            (std::cmp::Reverse(cell.hits), y, x)
+        });
+
+        // Opcodes are listed in a stable order when executed as many times
+        let mut opcodes: Vec<OpcodeHits> = Opcode::iter()
+            .filter_map(|opcode| {
+                self.executions
+                    .get(&opcode)
+                    .map(|&executions| OpcodeHits { opcode, executions })
+            })
+            .collect();
+        opcodes.sort_by_key(|opcode| std::cmp::Reverse(opcode.executions));
+
+        Report {
+            steps: self.steps,
+            stack_high_water: self.stack_high_water,
+            cells,
+            opcodes,
+            pointer_depths: self.pointer_depths.clone(),
+            coverage: self.coverage(grid),
+        }
+    }
+}
+
+/// Which non-empty cells of a grid were executed, see [`Profile::coverage()`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Coverage {
+    /// Number of non-empty cells
+    pub cells: usize,
+
+    /// Number of non-empty cells the head visited
+    pub executed: usize,
+
+    /// The non-empty cells the head never visited, row after row
+    pub never_executed: Vec<Position>,
+}
+
+/// A cell and the number of steps the head started on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CellHits {
+    pub position: Position,
+    pub content: Cell,
+    pub hits: u64,
+}
+
+/// An opcode and the number of times it was executed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OpcodeHits {
+    pub opcode: Opcode,
+    pub executions: u64,
+}
+
+/// A [`Profile`] summed up, see [`Profile::report()`]
+///
+/// Serialize it to export it as JSON, or [display](Display) it as a table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub steps: u64,
+    pub stack_high_water: usize,
+
+    /// Every visited cell, hottest first
+    pub cells: Vec<CellHits>,
+
+    /// Every executed opcode, most executed first
+    pub opcodes: Vec<OpcodeHits>,
+
+    /// Number of pointer resolutions, by number of pointers followed
+    pub pointer_depths: BTreeMap<usize, u64>,
+
+    pub coverage: Coverage,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "steps                 {}", self.steps)?;
+        writeln!(f, "stack high-water mark {}", self.stack_high_water)?;
+
+        if !self.pointer_depths.is_empty() {
+            let depths: Vec<String> = self
+                .pointer_depths
+                .iter()
+                .map(|(pointers, count)| format!("{pointers}: {count}"))
+                .collect();
+            writeln!(f, "pointer depths        {}", depths.join(", "))?;
+        }
+
+        writeln!(
+            f,
+            "coverage              {}/{} cells executed",
+            self.coverage.executed, self.coverage.cells
+        )?;
+        if !self.coverage.never_executed.is_empty() {
+            let positions: Vec<String> = self
+                .coverage
+                .never_executed
+                .iter()
+                .map(Position::to_string)
+                .collect();
+            writeln!(f, "never executed        {}", positions.join(" "))?;
+        }
+
+        if !self.cells.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "cell  {:>6}  content", "hits")?;
+            for cell in &self.cells {
+                writeln!(
+                    f,
+                    "{}    {:>6}  {}",
+                    cell.position,
+                    cell.hits,
+                    cell.content.as_str()
+                )?;
+            }
+        }
+
+        if !self.opcodes.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "opcode  {:>10}", "executions")?;
+            for opcode in &self.opcodes {
+                writeln!(
+                    f,
+                    "{:<6}  {:>10}",
+                    opcode.opcode.to_string(),
+                    opcode.executions
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Frame, position};
+
+    fn profile(text: &str, steps: usize) -> Frame {
+        let mut frame = Frame::from_text(text).unwrap();
+        frame.profile = Some(Profile::new());
+
+        for _ in 0..steps {
+            let _ = frame.step().unwrap();
+        }
+
+        frame
+    }
+
+    #[test]
+    fn counts() {
+        let frame = profile(
+            "A |&AC|&AD|add|jmp|\n\
+             B |5  |\n\
+             C |@AB|\n\
+             D |&AC|",
+            3,
+        );
+        let profile = frame.profile.as_ref().unwrap();
+
+        assert_eq!(profile.steps(), 3);
+        assert_eq!(profile.visits(position("AA")), 1);
+        assert_eq!(profile.visits(position("DA")), 0);
+        assert_eq!(profile.executions(Opcode::Add), 1);
+        assert_eq!(profile.executions(Opcode::Jmp), 0);
+        assert_eq!(profile.stack_high_water(), 2);
+
+        // `&AC` follows one pointer, `&AD` two
+        assert_eq!(profile.max_pointer_depth(), 2);
+        assert_eq!(profile.pointer_depths, BTreeMap::from([(1, 1), (2, 1)]));
+
+        let coverage = profile.coverage(&frame.grid);
+        assert_eq!(coverage.cells, 7);
+        assert_eq!(coverage.executed, 3);
+        assert_eq!(
+            coverage.never_executed,
+            ["DA", "AB", "AC", "AD"].map(position)
+        );
+    }
+
+    #[test]
+    fn report() {
+        let frame = profile("A |@AA|jmp|", 6);
+        let report = frame.profile.as_ref().unwrap().report(&frame.grid);
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "steps": 6,
+                "stack_high_water": 1,
+                "cells": [
+                    { "position": "AA", "content": "@AA", "hits": 3 },
+                    { "position": "BA", "content": "jmp", "hits": 3 },
+                ],
+                "opcodes": [{ "opcode": "jmp", "executions": 3 }],
+                "pointer_depths": {},
+                "coverage": { "cells": 2, "executed": 2, "never_executed": [] },
+            })
+        );
+
+        assert_eq!(
+            report.to_string(),
+            "steps                 6\n\
+             stack high-water mark 1\n\
+             coverage              2/2 cells executed\n\
+             \n\
+             cell    hits  content\n\
+             AA         3  @AA\n\
+             BA         3  jmp\n\
+             \n\
+             opcode  executions\n\
+             jmp              3\n"
+        );
+    }
+}
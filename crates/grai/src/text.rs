@@ -0,0 +1,700 @@
+//! The Graliffer text format (`.gra`), a human friendly way to write a [`Frame`]
+//!
+//! # Format
+//! A `.gra` source is read line by line, each line being either :
+//! - empty, or a comment starting with `;`, both are ignored
+//! - a grid row, in format `Y |abc|def|...|`, where `Y` is the vertical component of the row in
+//!   textual form (see [position representation](Position#representation)), followed by the
+//!   cells of that row separated by `|`, starting from the first column. Cells are left aligned,
+//!   trailing spaces are ignored so that columns can be aligned visually
+//! - a directive, a keyword followed by its arguments :
+//!   - `head XY direction`, the initial position and direction of the head, defaults to
+//!     `head AA right`
+//!   - `seed N`, the state of the [random number generator](crate::Random), defaults to `seed 0`
+//!   - `cell XY "content"`, an explicit cell, for contents that can't be written in a grid row
+//!     (containing `|`, trailing spaces or control characters), the content is quoted as a JSON
+//!     string
+//!   - `stack "abc" "@AA"`, the operands on the stack, from bottom to top, quoted as JSON strings
+//!   - `break XY`, a breakpoint
+//!   - `watch write XY:XY`, `watch read XY:XY` or `watch depth N`, a [watchpoint](Watchpoint) on
+//!     the writes or reads of a [region](crate::Region), or on the depth of the stack
+//!   - `protect XY:XY`, a [protected](crate::Protections) region, the program can not write to
+//!   - `comment XY "text"`, `label "name" XY:XY` or `tag XY #rrggbb`, an
+//!     [annotation](crate::Annotations), the text and name are quoted as JSON strings
+//!
+//! # Example
+//! ```text
+//! ; decrement the number at AB until it reaches 0
+//! head AA right
+//! ;   A   B   C   D   E   F   G   H   I   J
+//! A |@AB|1  |sub|@AB|set|@AB|0  |neq|@AA|jif|
+//! B |10 |
+//! ```
+//!
+//! [`print()`] produces sources that [`parse()`] reads back as the exact same frame, but for its
+//! [fuel](Frame::fuel), which limits a run rather than being part of the program.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+};
+
+use act::State;
+use unicode_segmentation::UnicodeSegmentation;
+use unwrap_infallible::UnwrapInfallible;
+
+use crate::{
+    AnnotationAction, Cell, CellError, Direction, Frame, Operand, Position, PositionError, Random,
+    TagColorError, Watchpoint, granary::GranaryDigit,
+};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error("line {line}: {kind}")]
+pub struct TextParseError {
+    pub line: usize,
+    pub kind: TextParseErrorKind,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum TextParseErrorKind {
+    #[error("unknown directive `{0}`")]
+    UnknownDirective(String),
+
+    #[error("missing {argument} for directive `{directive}`")]
+    MissingArgument {
+        directive: &'static str,
+        argument: &'static str,
+    },
+
+    #[error("unexpected argument `{got}` for directive `{directive}`")]
+    UnexpectedArgument {
+        directive: &'static str,
+        got: String,
+    },
+
+    #[error("invalid row `{got}`, expected a single character in textual representation")]
+    InvalidRow { got: char },
+
+    #[error("row `{got}` is already defined")]
+    DuplicatedRow { got: char },
+
+    #[error("too many cells in row, expected at most {}", GranaryDigit::MAX_NUMERIC + 1)]
+    RowTooLong,
+
+    #[error(transparent)]
+    Position(#[from] PositionError),
+
+    #[error("invalid direction `{got}`, expected one of `up`, `right`, `down` or `left`")]
+    Direction { got: String },
+
+    #[error(transparent)]
+    Cell(#[from] CellError),
+
+    #[error("invalid quoted string `{got}`")]
+    QuotedString { got: String },
+
+    #[error("invalid watchpoint `{got}`, expected `write XY:XY`, `read XY:XY` or `depth N`")]
+    Watchpoint { got: String },
+
+    #[error("invalid seed `{got}`, expected a number")]
+    Seed { got: String },
+
+    #[error(transparent)]
+    TagColor(#[from] TagColorError),
+}
+
+/// Parse a [`Frame`] from a `.gra` source, see the [module documentation](self) for the format
+///
+/// # Errors
+/// Returns an error, along with the line number (starting at 1), on the first line that could not
+/// be parsed
+pub fn parse(source: &str) -> Result<Frame, TextParseError> {
+    let mut parser = Parser::default();
+
+    for (index, line) in source.lines().enumerate() {
+        parser.line(line).map_err(|kind| TextParseError {
+            line: index + 1,
+            kind,
+        })?;
+    }
+
+    Ok(parser.frame)
+}
+
+/// Print a [`Frame`] as a `.gra` source, see the [module documentation](self) for the format
+pub fn print(frame: &Frame) -> String {
+    let mut source = String::new();
+
+    let head = &frame.head;
+    writeln!(source, "head {} {}", head.position, head.direction).unwrap();
+
+    if !frame.random.is_default() {
+        writeln!(source, "seed {}", frame.random.state()).unwrap();
+    }
+
+    if frame.stack.iter().next().is_some() {
+        source.push_str("stack");
+        for operand in frame.stack.iter() {
+            write!(source, " {}", quote(&operand.to_string())).unwrap();
+        }
+        source.push('\n');
+    }
+
+    let mut breakpoints: Vec<&Position> = frame.debug.breakpoints().collect();
+    breakpoints.sort_by_key(|position| (position.y(), position.x()));
+    for position in breakpoints {
+        writeln!(source, "break {position}").unwrap();
+    }
+
+    for region in frame.protections.regions() {
+        writeln!(source, "protect {region}").unwrap();
+    }
+
+    for watchpoint in frame.debug.watchpoints() {
+        match watchpoint {
+            Watchpoint::Write(region) => writeln!(source, "watch write {region}"),
+            Watchpoint::Read(region) => writeln!(source, "watch read {region}"),
+            Watchpoint::StackDepth(threshold) => writeln!(source, "watch depth {threshold}"),
+        }
+        .unwrap();
+    }
+
+    let mut rows: BTreeMap<u32, BTreeMap<u32, &Cell>> = BTreeMap::new();
+    let mut explicits = Vec::new();
+    for (position, cell) in frame.grid.cells() {
+        if cell.is_empty() {
+            continue;
+        } else if fits_in_row(cell) {
+            rows.entry(position.y())
+                .or_default()
+                .insert(position.x(), cell);
+        } else {
+            explicits.push((position, cell));
+        }
+    }
+
+    let columns = rows
+        .values()
+        .filter_map(|row| row.keys().max())
+        .max()
+        .map(|max| max + 1);
+
+    if let Some(columns) = columns {
+        source.push_str(";  ");
+        for x in 0..columns {
+            write!(source, " {}  ", digit_as_textual(x)).unwrap();
+        }
+        source.truncate(source.trim_end().len());
+        source.push('\n');
+    }
+
+    for (y, row) in rows {
+        write!(source, "{} |", digit_as_textual(y)).unwrap();
+
+        let columns = row.keys().max().map_or(0, |max| max + 1);
+        for x in 0..columns {
+            let content = row.get(&x).map_or("", |cell| cell.as_str());
+            let padding = 3_usize.saturating_sub(content.graphemes(true).count());
+
+            write!(source, "{content}{}|", " ".repeat(padding)).unwrap();
+        }
+        source.push('\n');
+    }
+
+    explicits.sort_by_key(|(position, _)| (position.y(), position.x()));
+    for (position, cell) in explicits {
+        writeln!(source, "cell {position} {}", quote(cell.as_str())).unwrap();
+    }
+
+    let annotations = &frame.annotations;
+
+    let mut comments: Vec<_> = annotations.comments().collect();
+    comments.sort_by_key(|(position, _)| (position.y(), position.x()));
+    for (position, comment) in comments {
+        writeln!(source, "comment {position} {}", quote(comment)).unwrap();
+    }
+
+    for (name, region) in annotations.labels() {
+        writeln!(source, "label {} {region}", quote(name)).unwrap();
+    }
+
+    let mut tags: Vec<_> = annotations.tags().collect();
+    tags.sort_by_key(|(position, _)| (position.y(), position.x()));
+    for (position, color) in tags {
+        writeln!(source, "tag {position} {color}").unwrap();
+    }
+
+    source
+}
+
+impl Frame {
+    /// Parse a `Frame` from a `.gra` source, see [`text::parse()`](parse)
+    pub fn from_text(source: &str) -> Result<Self, TextParseError> {
+        parse(source)
+    }
+
+    /// Print `self` as a `.gra` source, see [`text::print()`](print)
+    pub fn to_text(&self) -> String {
+        print(self)
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    frame: Frame,
+    rows: HashSet<u32>,
+}
+
+impl Parser {
+    fn line(&mut self, line: &str) -> Result<(), TextParseErrorKind> {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with(';') {
+            Ok(())
+        } else if let Some((row, cells)) = split_row(trimmed) {
+            self.row(row, cells)
+        } else {
+            let (directive, arguments) = trimmed
+                .split_once(char::is_whitespace)
+                .unwrap_or((trimmed, ""));
+
+            self.directive(directive, arguments.trim_start())
+        }
+    }
+
+    fn row(&mut self, row: char, cells: &str) -> Result<(), TextParseErrorKind> {
+        let y = GranaryDigit::from_textual(row)
+            .map_err(|_| TextParseErrorKind::InvalidRow { got: row })?
+            .as_numeric();
+
+        if !self.rows.insert(y) {
+            return Err(TextParseErrorKind::DuplicatedRow { got: row });
+        }
+
+        for (x, content) in cells.split('|').enumerate() {
+            let content = content.trim_end();
+
+            if content.is_empty() {
+                continue;
+            }
+
+            let x = u32::try_from(x).map_err(|_| TextParseErrorKind::RowTooLong)?;
+            let position =
+                Position::from_numeric(x, y).map_err(|_| TextParseErrorKind::RowTooLong)?;
+
+            self.frame.grid.set(position, Cell::new(content)?);
+        }
+
+        Ok(())
+    }
+
+    fn directive(&mut self, directive: &str, arguments: &str) -> Result<(), TextParseErrorKind> {
+        match directive {
+            "head" => {
+                let (position, rest) = next_argument(arguments, "head", "position")?;
+                let (direction, rest) = next_argument(rest, "head", "direction")?;
+                no_more_arguments(rest, "head")?;
+
+                self.frame.head.position = position.parse()?;
+                self.frame.head.direction =
+                    direction
+                        .parse::<Direction>()
+                        .map_err(|_| TextParseErrorKind::Direction {
+                            got: direction.to_string(),
+                        })?;
+            }
+            "seed" => {
+                let (seed, rest) = next_argument(arguments, "seed", "seed")?;
+                no_more_arguments(rest, "seed")?;
+
+                let seed = seed.parse().map_err(|_| TextParseErrorKind::Seed {
+                    got: seed.to_string(),
+                })?;
+                self.frame.random = Random::new(seed);
+            }
+            "cell" => {
+                let (position, rest) = next_argument(arguments, "cell", "position")?;
+                if rest.is_empty() {
+                    return Err(TextParseErrorKind::MissingArgument {
+                        directive: "cell",
+                        argument: "content",
+                    });
+                }
+
+                let (content, rest) = unquote(rest)?;
+                no_more_arguments(rest, "cell")?;
+
+                self.frame.grid.set(position.parse()?, Cell::new(&content)?);
+            }
+            "stack" => {
+                let mut operands = Vec::new();
+                let mut rest = arguments;
+
+                while !rest.is_empty() {
+                    let (content, next) = unquote(rest)?;
+                    operands.push(Operand::from_cell(Cell::new(&content)?));
+                    rest = next;
+                }
+
+                self.frame.stack = operands.into_iter().collect();
+            }
+            "break" => {
+                let (position, rest) = next_argument(arguments, "break", "position")?;
+                no_more_arguments(rest, "break")?;
+
+                self.frame.debug.set_breakpoint(position.parse()?);
+            }
+            "watch" => {
+                let (kind, rest) = next_argument(arguments, "watch", "kind")?;
+                let (target, rest) = next_argument(rest, "watch", "target")?;
+                no_more_arguments(rest, "watch")?;
+
+                let watchpoint = match kind {
+                    "write" => Watchpoint::Write(target.parse()?),
+                    "read" => Watchpoint::Read(target.parse()?),
+                    "depth" => Watchpoint::StackDepth(target.parse().map_err(|_| {
+                        TextParseErrorKind::Watchpoint {
+                            got: arguments.to_string(),
+                        }
+                    })?),
+                    _ => {
+                        return Err(TextParseErrorKind::Watchpoint {
+                            got: arguments.to_string(),
+                        });
+                    }
+                };
+
+                self.frame.debug.add_watchpoint(watchpoint);
+            }
+            "protect" => {
+                let (region, rest) = next_argument(arguments, "protect", "region")?;
+                no_more_arguments(rest, "protect")?;
+
+                self.frame.protections.protect(region.parse()?);
+            }
+            "comment" => {
+                let (position, rest) = next_argument(arguments, "comment", "position")?;
+                if rest.is_empty() {
+                    return Err(TextParseErrorKind::MissingArgument {
+                        directive: "comment",
+                        argument: "text",
+                    });
+                }
+
+                let (comment, rest) = unquote(rest)?;
+                no_more_arguments(rest, "comment")?;
+
+                self.annotate(AnnotationAction::Comment(position.parse()?, Some(comment)));
+            }
+            "label" => {
+                let (name, rest) = unquote(arguments)?;
+                let (region, rest) = next_argument(rest, "label", "region")?;
+                no_more_arguments(rest, "label")?;
+
+                self.annotate(AnnotationAction::Label(name, Some(region.parse()?)));
+            }
+            "tag" => {
+                let (position, rest) = next_argument(arguments, "tag", "position")?;
+                let (color, rest) = next_argument(rest, "tag", "color")?;
+                no_more_arguments(rest, "tag")?;
+
+                self.annotate(AnnotationAction::Tag(
+                    position.parse()?,
+                    Some(color.parse()?),
+                ));
+            }
+            _ => return Err(TextParseErrorKind::UnknownDirective(directive.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn annotate(&mut self, action: AnnotationAction) {
+        let _ = self.frame.annotations.act(action).unwrap_infallible();
+    }
+}
+
+/// Split a row line into its row character and its cells, if it is a row
+fn split_row(line: &str) -> Option<(char, &str)> {
+    let row = line.chars().next()?;
+    let cells = line[row.len_utf8()..].trim_start().strip_prefix('|')?;
+
+    Some((row, cells))
+}
+
+/// An error reading the arguments of a directive, shared with the [assembler](crate::asm)
+pub(crate) enum ArgumentError {
+    Missing {
+        directive: &'static str,
+        argument: &'static str,
+    },
+    Unexpected {
+        directive: &'static str,
+        got: String,
+    },
+    QuotedString {
+        got: String,
+    },
+}
+
+impl From<ArgumentError> for TextParseErrorKind {
+    fn from(value: ArgumentError) -> Self {
+        match value {
+            ArgumentError::Missing {
+                directive,
+                argument,
+            } => Self::MissingArgument {
+                directive,
+                argument,
+            },
+            ArgumentError::Unexpected { directive, got } => {
+                Self::UnexpectedArgument { directive, got }
+            }
+            ArgumentError::QuotedString { got } => Self::QuotedString { got },
+        }
+    }
+}
+
+/// Split the next whitespace separated argument of `directive` from the rest of `arguments`
+pub(crate) fn next_argument<'a>(
+    arguments: &'a str,
+    directive: &'static str,
+    argument: &'static str,
+) -> Result<(&'a str, &'a str), ArgumentError> {
+    if arguments.is_empty() {
+        return Err(ArgumentError::Missing {
+            directive,
+            argument,
+        });
+    }
+
+    let (next, rest) = arguments
+        .split_once(char::is_whitespace)
+        .unwrap_or((arguments, ""));
+
+    Ok((next, rest.trim_start()))
+}
+
+pub(crate) fn no_more_arguments(rest: &str, directive: &'static str) -> Result<(), ArgumentError> {
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(ArgumentError::Unexpected {
+            directive,
+            got: rest.to_string(),
+        })
+    }
+}
+
+/// Read a JSON string at the start of `arguments`, returning its content and the rest
+pub(crate) fn unquote(arguments: &str) -> Result<(String, &str), ArgumentError> {
+    let mut stream = serde_json::Deserializer::from_str(arguments).into_iter::<String>();
+
+    match stream.next() {
+        Some(Ok(content)) => Ok((content, arguments[stream.byte_offset()..].trim_start())),
+        _ => Err(ArgumentError::QuotedString {
+            got: arguments.to_string(),
+        }),
+    }
+}
+
+fn quote(content: &str) -> String {
+    serde_json::to_string(content).expect("a string should always be serializable")
+}
+
+/// Returns `true` if the content of the [`Cell`] can be written in a grid row
+fn fits_in_row(cell: &Cell) -> bool {
+    let content = cell.as_str();
+
+    !content.contains('|')
+        && content.trim_end() == content
+        && !content.chars().any(char::is_control)
+}
+
+fn digit_as_textual(value: u32) -> char {
+    GranaryDigit::numeric_to_textual(value).expect("grid coordinates are valid granary digits")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TagColor, examples};
+
+    fn as_json(frame: &Frame) -> serde_json::Value {
+        serde_json::to_value(frame).unwrap()
+    }
+
+    fn assert_round_trip(frame: &Frame) {
+        let source = print(frame);
+        let parsed = parse(&source).unwrap_or_else(|err| panic!("{err} in:\n{source}"));
+
+        assert_eq!(as_json(&parsed), as_json(frame), "in:\n{source}");
+    }
+
+    #[test]
+    fn parse_rows() -> Result<(), TextParseError> {
+        let frame = parse(
+            "; a comment\n\
+             head BA down\n\
+             A |100|&BB|div|\n\
+             \n\
+             C |   |3  |\n",
+        )?;
+
+        assert_eq!(frame.head.position, "BA".parse().unwrap());
+        assert_eq!(frame.head.direction, Direction::Down);
+        assert_eq!(frame.grid.get("AA".parse().unwrap()).as_str(), "100");
+        assert_eq!(frame.grid.get("CA".parse().unwrap()).as_str(), "div");
+        assert!(frame.grid.get("AC".parse().unwrap()).is_empty());
+        assert_eq!(frame.grid.get("BC".parse().unwrap()).as_str(), "3");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_directives() -> Result<(), TextParseError> {
+        let frame = parse(
+            "cell Ab \"a|b\"\n\
+             stack \"1\" \"@AA\"\n\
+             break DE\n\
+             watch write AA:CB\n\
+             watch depth 4\n\
+             seed 42\n\
+             protect AB:CD\n\
+             comment AA \"start\"\n\
+             label \"data\" AB:CD\n\
+             tag AA #ff8800\n",
+        )?;
+
+        assert_eq!(frame.grid.get("Ab".parse().unwrap()).as_str(), "a|b");
+        assert_eq!(
+            frame
+                .stack
+                .iter()
+                .map(|op| op.to_string())
+                .collect::<Vec<_>>(),
+            vec!["1", "@AA"]
+        );
+        assert!(frame.debug.has_breakpoint("DE".parse().unwrap()));
+        assert_eq!(
+            frame.debug.watchpoints().collect::<Vec<_>>(),
+            [
+                &Watchpoint::Write("AA:CB".parse().unwrap()),
+                &Watchpoint::StackDepth(4)
+            ]
+        );
+        assert_eq!(frame.random, Random::new(42));
+        assert_eq!(frame.protections.regions(), ["AB:CD".parse().unwrap()]);
+
+        let annotations = &frame.annotations;
+        assert_eq!(annotations.comment("AA".parse().unwrap()), Some("start"));
+        assert_eq!(annotations.label("data"), Some("AB:CD".parse().unwrap()));
+        assert_eq!(
+            annotations.tag("AA".parse().unwrap()),
+            Some(TagColor::new(0xff, 0x88, 0x00))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("head AA right\nhop").unwrap_err(),
+            TextParseError {
+                line: 2,
+                kind: TextParseErrorKind::UnknownDirective("hop".to_string())
+            }
+        );
+
+        assert_eq!(
+            parse("head AA").unwrap_err().kind,
+            TextParseErrorKind::MissingArgument {
+                directive: "head",
+                argument: "direction"
+            }
+        );
+
+        assert_eq!(
+            parse("head AA sideway").unwrap_err().kind,
+            TextParseErrorKind::Direction {
+                got: "sideway".to_string()
+            }
+        );
+
+        assert_eq!(
+            parse("A |abcd|").unwrap_err().kind,
+            TextParseErrorKind::Cell(CellError::InvalidCellFormat("abcd".to_string()))
+        );
+
+        assert_eq!(
+            parse("A |a|\nA |b|").unwrap_err().kind,
+            TextParseErrorKind::DuplicatedRow { got: 'A' }
+        );
+
+        assert_eq!(
+            parse(&format!("A {}", "|a".repeat(65))).unwrap_err().kind,
+            TextParseErrorKind::RowTooLong
+        );
+
+        assert!(matches!(
+            parse("cell AA abc").unwrap_err().kind,
+            TextParseErrorKind::QuotedString { .. }
+        ));
+
+        assert!(matches!(
+            parse("tag AA f80").unwrap_err().kind,
+            TextParseErrorKind::TagColor(_)
+        ));
+
+        assert_eq!(
+            parse("seed -1").unwrap_err().kind,
+            TextParseErrorKind::Seed {
+                got: "-1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn print_layout() {
+        let frame = parse("head AA right\nA |@AB|1|sub|\nC |10|").unwrap();
+
+        assert_eq!(
+            print(&frame),
+            "head AA right\n\
+             ;   A   B   C\n\
+             A |@AB|1  |sub|\n\
+             C |10 |\n"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for name in examples::names() {
+            assert_round_trip(&Frame::from_example(name).unwrap());
+        }
+
+        let frame = parse(
+            "head /+ left\n\
+             seed 18446744073709551615\n\
+             stack \"\\\"\" \"###\"\n\
+             break AA\n\
+             watch read BB\n\
+             watch write CD:AA\n\
+             protect BA:CA\n\
+             protect AA\n\
+             comment BA \"a \\\"quoted\\\" comment\"\n\
+             comment AA \"\"\n\
+             label \"two words\" AA:BA\n\
+             label \"all\" AA:/+\n\
+             tag BA #123456\n\
+             tag AA #000000\n\
+             cell AA \"|||\"\n\
+             cell BA \"a \"\n\
+             cell CA \"\\n\"\n\
+             / |é  |  x|",
+        )
+        .unwrap();
+        assert_round_trip(&frame);
+    }
+}
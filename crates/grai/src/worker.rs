@@ -0,0 +1,411 @@
+//! Run a [`Frame`] on a background thread
+//!
+//! A [`Worker`] steps a [`SharedFrame`] on its own thread, taking the frame's lock for a small
+//! batch of steps at a time, so that other threads can read the frame (or take a
+//! [snapshot](Worker::snapshot)) while it runs. It is driven by commands sent from any thread :
+//! pause, resume, step once, and stop.
+//!
+//! The worker pauses by itself when running the frame breaks off, see [`Break`]. It
+//! finishes when stopped, or when a step errors.
+//!
+//! # Examples
+//! ```
+//! # use grai::{Break, Frame, SharedFrame, worker::{Pause, Status, Worker}};
+//! let frame = SharedFrame::new(Frame::from_example("countdown").unwrap());
+//! let worker = Worker::spawn(frame.clone());
+//!
+//! assert_eq!(worker.wait(), Status::Paused(Pause::Break(Break::Halted)));
+//! frame.read(|frame| assert_eq!(frame.grid.get("AB".parse().unwrap()).as_str(), "0"));
+//!
+//! let steps = worker.stop().unwrap();
+//! assert!(steps > 0);
+//! ```
+
+use std::{
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard,
+        mpsc::{self, Receiver, Sender, TryRecvError},
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{Break, FrameError, SharedFrame, interpreter, snapshot::SnapshotError};
+
+/// A command sent to a [`Worker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Pause,
+    Resume,
+
+    /// Step once, only when paused
+    Step,
+
+    Stop,
+}
+
+/// Why a [`Worker`] is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pause {
+    /// A [`Command::Pause`] was received, or the worker was spawned paused
+    Requested,
+
+    Break(Break),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Running,
+    Paused(Pause),
+
+    /// The worker was stopped, or a step errored, see [`Worker::join()`]
+    Finished,
+}
+
+/// The status of a worker, along with a way to wait for it to change
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<SharedState>,
+    changed: Condvar,
+}
+
+#[derive(Debug)]
+struct SharedState {
+    status: Status,
+
+    /// Number of commands sent but not handled yet
+    pending: usize,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, SharedState> {
+        self.state
+            .lock()
+            .expect("the worker state lock should not be poisoned")
+    }
+
+    fn set(&self, status: Status) {
+        self.lock().status = status;
+        self.changed.notify_all();
+    }
+
+    fn handled(&self) {
+        self.lock().pending -= 1;
+        self.changed.notify_all();
+    }
+}
+
+/// Runs a [`SharedFrame`] on a background thread, see the [module documentation](self)
+#[derive(Debug)]
+pub struct Worker {
+    frame: SharedFrame,
+    commands: Sender<Command>,
+    shared: Arc<Shared>,
+    thread: JoinHandle<Result<u64, FrameError>>,
+}
+
+impl Worker {
+    /// Number of steps run while holding the frame's lock
+    const BATCH: usize = 256;
+
+    /// Start running `frame` on a new thread
+    pub fn spawn(frame: SharedFrame) -> Self {
+        Self::spawn_with(frame, Status::Running)
+    }
+
+    /// Same as [`Worker::spawn()`], but start paused, waiting for a command
+    pub fn spawn_paused(frame: SharedFrame) -> Self {
+        Self::spawn_with(frame, Status::Paused(Pause::Requested))
+    }
+
+    fn spawn_with(frame: SharedFrame, status: Status) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(SharedState { status, pending: 0 }),
+            changed: Condvar::new(),
+        });
+
+        let thread = {
+            let frame = frame.clone();
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || run(frame, receiver, &shared, status))
+        };
+
+        Self {
+            frame,
+            commands,
+            shared,
+            thread,
+        }
+    }
+
+    /// The frame being run
+    pub fn frame(&self) -> &SharedFrame {
+        &self.frame
+    }
+
+    /// A [snapshot](crate::snapshot) of the frame, as it is between two batches of steps
+    pub fn snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        self.frame.read(|frame| frame.to_snapshot())
+    }
+
+    pub fn status(&self) -> Status {
+        self.shared.lock().status
+    }
+
+    /// Block until every command sent is handled, and the worker is no longer running, returns its
+    /// status
+    pub fn wait(&self) -> Status {
+        self.shared
+            .changed
+            .wait_while(self.shared.lock(), |state| {
+                state.status != Status::Finished
+                    && (state.pending > 0 || state.status == Status::Running)
+            })
+            .expect("the worker state lock should not be poisoned")
+            .status
+    }
+
+    /// Send a command to the worker, commands sent after it finished are ignored
+    pub fn send(&self, command: Command) {
+        let mut state = self.shared.lock();
+
+        // The worker only drops the receiver when finished
+        if self.commands.send(command).is_ok() {
+            state.pending += 1;
+        }
+    }
+
+    pub fn pause(&self) {
+        self.send(Command::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.send(Command::Resume);
+    }
+
+    pub fn step(&self) {
+        self.send(Command::Step);
+    }
+
+    /// Stop the worker and wait for it to finish, see [`Worker::join()`]
+    pub fn stop(self) -> Result<u64, FrameError> {
+        self.send(Command::Stop);
+        self.join()
+    }
+
+    /// Wait for the worker to finish, returns the number of steps it ran
+    ///
+    /// # Errors
+    /// Returns the error of the step that failed, if any
+    pub fn join(self) -> Result<u64, FrameError> {
+        self.thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+/// The worker thread
+fn run(
+    mut frame: SharedFrame,
+    commands: Receiver<Command>,
+    shared: &Shared,
+    mut status: Status,
+) -> Result<u64, FrameError> {
+    let mut steps = 0;
+
+    let result = loop {
+        let command = if status == Status::Running {
+            match commands.try_recv() {
+                Ok(command) => Some(command),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => break Ok(steps),
+            }
+        } else {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => break Ok(steps),
+            }
+        };
+
+        let budget = match (command, status) {
+            (Some(Command::Stop), _) => break Ok(steps),
+            (Some(Command::Pause), _) => {
+                status = Status::Paused(Pause::Requested);
+                shared.set(status);
+                0
+            }
+            (Some(Command::Resume), _) => {
+                status = Status::Running;
+                shared.set(status);
+                0
+            }
+            (Some(Command::Step), Status::Paused(_)) => 1,
+            (Some(Command::Step), _) => 0,
+            (None, _) => Worker::BATCH,
+        };
+
+        let batch = frame.write(|frame| {
+            for _ in 0..budget {
+                let stepped = interpreter::step(frame, |_, err| Err(err))?;
+                if stepped.revert.is_some() {
+                    steps += 1;
+                }
+
+                if let Some(stop) = stepped.stop {
+                    return Ok(Some(Pause::Break(stop)));
+                }
+            }
+
+            Ok(None)
+        });
+
+        match batch {
+            Ok(Some(pause)) => {
+                status = Status::Paused(pause);
+                shared.set(status);
+            }
+            Ok(None) if budget == 1 => {
+                status = Status::Paused(Pause::Requested);
+                shared.set(status);
+            }
+            Ok(None) => {}
+            Err(err) => break Err(err),
+        }
+
+        if command.is_some() {
+            shared.handled();
+        }
+    };
+
+    shared.set(Status::Finished);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Frame, Fuel, Watchpoint, position};
+
+    #[test]
+    fn thread_safe() {
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Frame>();
+        assert_send_sync::<SharedFrame>();
+        assert_send::<act::Revert>();
+    }
+
+    #[test]
+    fn pause_resume_stop() {
+        let frame = SharedFrame::new(Frame::from_example("loop").unwrap());
+        let worker = Worker::spawn_paused(frame);
+        assert_eq!(worker.status(), Status::Paused(Pause::Requested));
+
+        worker.step();
+        worker.step();
+        worker.pause();
+        assert_eq!(worker.wait(), Status::Paused(Pause::Requested));
+
+        worker.resume();
+        worker.pause();
+        assert_eq!(worker.wait(), Status::Paused(Pause::Requested));
+
+        let snapshot = Frame::from_snapshot(&worker.snapshot().unwrap()).unwrap();
+        assert!(worker.frame().read(|frame| frame.head.position) == snapshot.head.position);
+
+        assert!(worker.stop().unwrap() >= 2);
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut frame = Frame::from_example("countdown").unwrap();
+        frame.debug.set_breakpoint(position("JA"));
+
+        let worker = Worker::spawn(SharedFrame::new(frame));
+        assert_eq!(
+            worker.wait(),
+            Status::Paused(Pause::Break(Break::Breakpoint))
+        );
+        assert_eq!(
+            worker.frame().read(|frame| frame.head.position),
+            position("JA")
+        );
+
+        // Step off the breakpoint, and run up to it again
+        worker.step();
+        worker.resume();
+        assert_eq!(
+            worker.wait(),
+            Status::Paused(Pause::Break(Break::Breakpoint))
+        );
+        assert_eq!(
+            worker
+                .frame()
+                .read(|frame| frame.grid.get(position("AB")).to_string()),
+            "8"
+        );
+
+        worker.stop().unwrap();
+    }
+
+    #[test]
+    fn watchpoints() {
+        let mut frame = Frame::from_example("countdown").unwrap();
+        frame
+            .debug
+            .add_watchpoint(Watchpoint::Write(position("AB").into()));
+
+        let worker = Worker::spawn(SharedFrame::new(frame));
+        assert_eq!(
+            worker.wait(),
+            Status::Paused(Pause::Break(Break::Watchpoint))
+        );
+        worker.frame().read(|frame| {
+            assert_eq!(frame.grid.get(position("AB")).as_str(), "9");
+            assert_eq!(frame.watch_hits().len(), 1);
+        });
+
+        worker.stop().unwrap();
+    }
+
+    #[test]
+    fn out_of_fuel() {
+        let mut frame = Frame::from_example("countdown").unwrap();
+        frame.fuel = Some(Fuel::new(20));
+
+        let mut worker_frame = SharedFrame::new(frame);
+        let worker = Worker::spawn(worker_frame.clone());
+        assert_eq!(
+            worker.wait(),
+            Status::Paused(Pause::Break(Break::OutOfFuel))
+        );
+        assert_ne!(
+            worker
+                .frame()
+                .read(|frame| frame.grid.get(position("AB")).to_string()),
+            "0"
+        );
+
+        worker_frame.write(|frame| frame.fuel.as_mut().unwrap().refuel(10_000));
+        worker.resume();
+        assert_eq!(worker.wait(), Status::Paused(Pause::Break(Break::Halted)));
+        assert_eq!(
+            worker
+                .frame()
+                .read(|frame| frame.grid.get(position("AB")).to_string()),
+            "0"
+        );
+
+        worker.stop().unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let frame = Frame::from_text("A |jmp|").unwrap();
+
+        let worker = Worker::spawn(SharedFrame::new(frame));
+        assert_eq!(worker.wait(), Status::Finished);
+        assert!(worker.join().is_err());
+    }
+}
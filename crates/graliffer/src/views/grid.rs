@@ -3,7 +3,7 @@ use std::convert::Infallible;
 use act::{Action, Revert, State};
 use crossterm::event::{MouseEvent, MouseEventKind};
 use grai::{Direction, HorizontalDirection, VerticalDirection, granary::GranaryDigit};
-use log::debug;
+use log::{debug, warn};
 use ratatui::{
     buffer::Buffer,
     layout::{Margin, Offset, Position, Rect, Size},
@@ -53,6 +53,9 @@ enum GridCursorPosition {
 struct GridInput {
     grid_cursor: grai::Position,
     input: Input,
+
+    /// Whether a cell was changed since the last [`GridInput::take_edited()`]
+    edited: bool,
 }
 
 impl GridInput {
@@ -60,6 +63,7 @@ impl GridInput {
         let mut grid_input = Self {
             input: Input::default(),
             grid_cursor: grai::Position::default(),
+            edited: false,
         };
 
         grid_input.sync_input(grid);
@@ -100,10 +104,20 @@ impl GridInput {
 
     pub fn handle(&mut self, grid: &mut grai::Grid, input_request: InputRequest) {
         self.input.handle(input_request);
-        grid.set(self.grid_cursor, grai::Cell::new_trim(self.input.value()));
+
+        let cell = grai::Cell::new_trim(self.input.value());
+        if grid.get(self.grid_cursor) != cell {
+            grid.set(self.grid_cursor, cell);
+            self.edited = true;
+        }
+
         self.sync_input(grid);
     }
 
+    pub fn take_edited(&mut self) -> bool {
+        std::mem::take(&mut self.edited)
+    }
+
     pub fn with_movement(&mut self, movement: CursorMovement, grid: &grai::Grid) {
         let at_start = self.char_at_start();
         let at_end = self.char_at_end(grid);
@@ -296,6 +310,7 @@ pub struct GridView {
     context: Context,
 
     frame: grai::FrameGuard,
+    history: grai::History,
 
     grid_input: GridInput,
 
@@ -313,6 +328,7 @@ impl GridView {
         GridView {
             context,
             frame,
+            history: grai::History::new(),
 
             grid_input,
 
@@ -473,14 +489,28 @@ impl StatefulWidget for GridWidget {
                 let cell_area =
                     Rect::new(x, y, bordered_cell_size.width, bordered_cell_size.height);
 
+                let (cell_content, is_head, is_breakpoint) = state.frame.read(|frame| {
+                    (
+                        frame.grid.get(grid_pos),
+                        frame.head.position == grid_pos,
+                        frame.debug.has_breakpoint(grid_pos),
+                    )
+                });
+
+                let border_color = if is_head {
+                    Color::Yellow
+                } else if is_breakpoint {
+                    Color::Red
+                } else {
+                    Color::DarkGray
+                };
+
                 let block = Block::bordered()
                     // .borders(borders)
                     // .border_type(border_type)
-                    .fg(Color::DarkGray)
+                    .fg(border_color)
                     .merge_borders(MergeStrategy::Fuzzy);
 
-                let cell_content = state.frame.read(|frame| frame.grid.get(grid_pos));
-
                 Paragraph::new(cell_content.as_str())
                     .block(block)
                     .reset()
@@ -586,6 +616,11 @@ pub enum GridAction {
     CursorDashDownCharThenGrid,
     CursorDashRightCharThenGrid,
     CursorDashLeftCharThenGrid,
+
+    Step,
+    StepBack,
+    StepBackToBreakpoint,
+    ToggleBreakpoint,
 }
 
 impl Action for GridAction {}
@@ -699,7 +734,45 @@ impl State for GridView {
                     );
                 })
             }
+
+            Step => {
+                if let Err(err) = self.frame.write(|frame| self.history.step(frame)) {
+                    warn!("could not step the frame: {err}");
+                }
+            }
+
+            StepBack => {
+                if let Err(err) = self.frame.write(|frame| self.history.step_back(frame)) {
+                    warn!("could not step back the frame: {err}");
+                }
+            }
+
+            StepBackToBreakpoint => {
+                if let Err(err) = self
+                    .frame
+                    .write(|frame| self.history.step_back_to_breakpoint(frame))
+                {
+                    warn!("could not step back the frame: {err}");
+                }
+            }
+
+            ToggleBreakpoint => {
+                let position = *self.grid_input.grid_position();
+
+                if let Err(err) = self
+                    .frame
+                    .act(grai::FrameAction::ToggleBreakpoint(position))
+                {
+                    warn!("could not toggle breakpoint: {err}");
+                }
+            }
         }
+
+        // Stepping back over an edit would overwrite it with what the step had read
+        if self.grid_input.take_edited() {
+            self.history.clear();
+        }
+
         Ok(Revert::None)
     }
 }